@@ -1,20 +1,71 @@
 use super::*;
+use super::super::dict::Dict;
+use super::super::protocol::{Scores, ServerMessage};
 
-use std::iter::FromIterator;
+use rand::{self, Rng, SeedableRng, Isaac64Rng};
+
+/// Mot soumis par un joueur, avec la trajectoire utilisée pour le former.
+#[derive(Debug, Clone, PartialEq)]
+struct PlayedWord {
+    word: String,
+    trajectory: Vec<Cell>,
+}
+
+/// Nombre maximum de mots listés dans le message MANQUES.
+const MAX_MISSED_WORDS: usize = 10;
 
 pub struct Board {
-    /// Grille de jeu
-    grid: [char; 16],
-    /// Grilles de test fournie au lancement du serveur
-    grids: Vec<String>,
+    /// Grille de jeu, chaque case contenant une ou plusieurs lettres.
+    grid: Vec<String>,
+    /// Nombre de lignes et de colonnes de la grille.
+    size: usize,
+    /// Dés utilisés pour générer les grilles aléatoires.
+    dices: Dices,
+    /// Graine fixée au lancement du serveur, si elle existe.
+    fixed_seed: Option<u64>,
+    /// Graine de la session en cours, dont sont dérivées les graines de chaque tour.
+    seed: u64,
+    /// Générateur des graines de tour de la session en cours.
+    rng: Isaac64Rng,
+    /// Graine ayant servi à générer la grille courante.
+    turn_seed: u64,
+    /// Critères de qualité des grilles aléatoires.
+    filter: GridFilter,
+    /// Règle de calcul des points rapportés par chaque mot.
+    scoring: Box<dyn ScoringRule>,
+    /// Nombre de cases bonus placées au hasard sur les grilles sans disposition fixée.
+    nb_bonus: usize,
+    /// Disposition des cases bonus de la grille courante.
+    bonus: BonusLayout,
+    /// Mots pouvant être formés sur la grille courante.
+    solutions: Vec<Solution>,
+    /// Catalogue des grilles à jouer à la place des grilles aléatoires.
+    catalogue: Catalogue,
+    /// Description de la grille courante, si elle provient du catalogue.
+    description: Option<String>,
     /// HashMap associant chaque joueur à son score.
     scores: HashMap<String, u32>,
     /// HashMap associant chaque joueur aux mots qu'il a soumis.
-    player_words: HashMap<String, Vec<String>>,
+    player_words: HashMap<String, Vec<PlayedWord>>,
+    /// Nombre de points retirés à un joueur pour chaque indice demandé.
+    hint_penalty: u32,
+    /// HashMap associant chaque joueur aux indices qu'il a reçus lors de ce tour.
+    hints: HashMap<String, HintState>,
+    /// HashMap associant chaque joueur aux points perdus en demandant des indices lors de ce tour.
+    penalties: HashMap<String, u32>,
     /// Set contennant tous les mots joués lors de ce tours.
     played: HashSet<String>,
     /// Set contenant tous les mots joués au moins deux fois lors de ce tours.
     invalid_words: HashSet<String>,
+    /// HashMap associant chaque joueur aux mots qu'il a soumis lors de ce tour et que le
+    /// dictionnaire a refusés, qu'il peut contester pendant la pause.
+    rejected_words: HashMap<String, Vec<PlayedWord>>,
+    /// Si true, un joueur peut aussi contester les mots acceptés de ses adversaires.
+    opponent_challenges: bool,
+    /// Contestations en attente de verdict.
+    challenges: Vec<Challenge>,
+    /// Set contenant les contestations ouvertes lors de ce tour, par joueur et par mot.
+    challenged: HashSet<(String, String)>,
     /// Si true, la verrification immédiate est activée.
     immediate: bool,
     /// Numéro du tours en cours.
@@ -22,58 +73,194 @@ pub struct Board {
 }
 
 impl Board {
-    pub fn new(immediate: bool, grids: Vec<String>) -> Board {
+    pub fn new(immediate: bool) -> Board {
+        let seed = rand::random();
         Board {
-            grid: ['A'; 16],
-            grids,
+            grid: vec!["A".to_string(); 16],
+            size: 4,
+            dices: Dices::new(4),
+            fixed_seed: None,
+            seed,
+            rng: Isaac64Rng::from_seed(&[seed][..]),
+            turn_seed: 0,
+            filter: GridFilter::default(),
+            scoring: Box::new(Classic),
+            nb_bonus: 0,
+            bonus: BonusLayout::empty(4),
+            solutions: vec![],
+            catalogue: Catalogue::new(),
+            description: None,
             scores: HashMap::new(),
             player_words: HashMap::new(),
+            hint_penalty: 1,
+            hints: HashMap::new(),
+            penalties: HashMap::new(),
             played: HashSet::new(),
             invalid_words: HashSet::new(),
+            rejected_words: HashMap::new(),
+            opponent_challenges: false,
+            challenges: vec![],
+            challenged: HashSet::new(),
             immediate,
             turn: 0,
         }
     }
 
 
-    fn update_grid(&mut self) {
-        let grid = match self.next_grid() {
-            Some(grid) => grid,
-            None => generate_random_grid(),
-        };
-        self.grid = grid;
+    /// Définit le nombre de lignes et de colonnes de la grille.
+    /// Les dés français correspondant à cette taille sont utilisés par défaut.
+    pub fn with_size(mut self, size: usize) -> Board {
+        self.size = size;
+        self.grid = vec!["A".to_string(); size * size];
+        self.dices = Dices::new(size);
+        self.bonus = BonusLayout::empty(size);
+        self
     }
 
-    fn next_grid(&mut self) -> Option<[char; 16]> {
-        if self.grids.is_empty() {
-            None
-        } else {
-            let grid = self.grids.remove(0);
-            self.grids.push(grid.clone());
-            grid_of_string(&grid)
+    /// Définit les dés utilisés pour générer les grilles aléatoires.
+    /// `dices` doit contenir autant de dés que la grille contient de cases.
+    pub fn with_dices(mut self, dices: Dices) -> Board {
+        self.dices = dices;
+        self
+    }
+
+    /// Fixe la graine des sessions: chaque session produira la même suite de grilles.
+    pub fn with_seed(mut self, seed: u64) -> Board {
+        self.fixed_seed = Some(seed);
+        self.reseed();
+        self
+    }
+
+    /// Réinitialise le générateur de graines de tour pour une nouvelle session.
+    fn reseed(&mut self) {
+        self.seed = self.fixed_seed.unwrap_or_else(rand::random);
+        self.rng = Isaac64Rng::from_seed(&[self.seed][..]);
+    }
+
+    /// Définit les critères de qualité des grilles aléatoires.
+    pub fn with_filter(mut self, filter: GridFilter) -> Board {
+        self.filter = filter;
+        self
+    }
+
+    /// Définit la règle de calcul des points rapportés par chaque mot.
+    pub fn with_scoring(mut self, scoring: Box<dyn ScoringRule>) -> Board {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Définit le nombre de cases bonus placées au hasard sur chaque grille,
+    /// sauf si le catalogue fixe la disposition des bonus de la grille.
+    pub fn with_bonus(mut self, nb_bonus: usize) -> Board {
+        self.nb_bonus = nb_bonus;
+        self
+    }
+
+    /// Définit le nombre de points retirés à un joueur pour chaque indice demandé.
+    pub fn with_hint_penalty(mut self, penalty: u32) -> Board {
+        self.hint_penalty = penalty;
+        self
+    }
+
+    /// Définit le catalogue des grilles à jouer à la place des grilles aléatoires.
+    pub fn with_catalogue(mut self, catalogue: Catalogue) -> Board {
+        self.catalogue = catalogue;
+        self
+    }
+
+    /// Permet ou non aux joueurs de contester les mots acceptés de leurs adversaires.
+    pub fn with_opponent_challenges(mut self, enabled: bool) -> Board {
+        self.opponent_challenges = enabled;
+        self
+    }
+
+    /// Renvoie la description de la grille courante, si elle provient du catalogue.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Renvoie la graine de la session en cours.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Renvoie les statistiques de la grille courante.
+    pub fn stats(&self) -> GridStats {
+        GridStats::new(self.turn_seed, &self.solutions)
+    }
+
+    /// Remplace la grille courante.
+    /// Les grilles aléatoires ne respectant pas les critères de qualité sont rejetées
+    /// et tirées à nouveau; les statistiques des grilles rejetées sont renvoyées.
+    fn update_grid(&mut self, dict: &dyn Dict) -> Vec<GridStats> {
+        let mut rejected = vec![];
+        loop {
+            self.turn_seed = self.rng.gen();
+            self.description = None;
+            let (grid, bonus, random) = match self.catalogue.next_entry(&mut self.rng) {
+                Some(entry) => {
+                    self.turn_seed = entry.seed.unwrap_or(self.turn_seed);
+                    self.description = Some(entry.to_string());
+                    (entry.grid.clone(), entry.bonus.clone(), false)
+                },
+                None => (self.dices.roll(self.turn_seed), None, true),
+            };
+            let solutions = solver::solve(&grid, self.size, dict, &*self.scoring);
+            let stats = GridStats::new(self.turn_seed, &solutions);
+
+            if random && ! self.filter.accepts(&stats) && rejected.len() < self.filter.max_rejections {
+                rejected.push(stats);
+            } else {
+                self.grid = grid;
+                self.solutions = solutions;
+                self.bonus = bonus.unwrap_or_else(|| BonusLayout::random(self.turn_seed, self.size, self.nb_bonus));
+                return rejected
+            }
         }
     }
 
     /// "Mise à zéro" du plateau de jeu après une tour.
+    /// La grille de la session suivante n'est tirée qu'à son premier tour, une fois la graine renouvelée.
     pub fn reset(&mut self) {
-        self.update_grid();
+        self.reseed();
+        self.catalogue.rewind();
         self.invalid_words.clear();
         self.scores.values_mut().for_each(|v| *v = 0);
         self.player_words.clear();
+        self.hints.clear();
+        self.penalties.clear();
         self.played.clear();
+        self.clear_challenges();
         self.turn = 1;
     }
 
-    /// Renvoie une chaîne de caractères contenant le message de bienvenue.
-    pub fn welcome_str(&self) -> String {
-        let grid = String::from_iter(self.grid.iter());
-        let scores = self.scores_str();
-        format!("BIENVENUE/{}/{}*{}/\n", grid, self.turn, scores)
+    /// Renvoie le message de bienvenue.
+    pub fn welcome_message(&self) -> ServerMessage {
+        ServerMessage::Welcome {
+            grid: self.grid_str(),
+            turn: self.turn,
+            scores: self.scores(),
+            bonus: self.bonus_str(),
+        }
+    }
+
+    /// Renvoie le message de début de tour.
+    pub fn turn_message(&self) -> ServerMessage {
+        ServerMessage::Turn { grid: self.grid_str(), bonus: self.bonus_str() }
+    }
+
+    /// Renvoie la disposition des cases bonus, ou `None` si la grille n'a aucune case bonus.
+    fn bonus_str(&self) -> Option<String> {
+        if self.bonus.is_empty() {
+            None
+        } else {
+            Some(self.bonus.to_string())
+        }
     }
 
     /// Renvoie une chaîne de charactères représentant le cube de boggle.
     pub fn grid_str(&self) -> String {
-        String::from_iter(self.grid.iter())
+        string_of_grid(&self.grid)
     }
 
     /// Ajoute l'utilisateur `username` et lui aattribue un score de 0.
@@ -87,104 +274,284 @@ impl Board {
         self.scores.remove(username);
     }
 
-    /// Renvoie une chaîne de caractères représentant les scores des joueurs.
-    pub fn scores_str(&self) -> String  {
-        self.scores.keys()
-            .map(|u| format!("{}*{}", u, self.user_score(u)))
-            .collect::<Vec<String>>()
-            .join("*")
+    /// Renvoie les scores des joueurs.
+    pub fn scores(&self) -> Scores {
+        self.scores.keys().map(|u| (u.clone(), self.user_score(u))).collect()
     }
 
-    /// Renvoie une chaîne de caractères représentant les mot joués par les joueurs.
-    fn words_str(&self) -> String {
+    /// Renvoie les mots joués par chaque joueur.
+    fn words(&self) -> Vec<(String, Vec<String>)> {
         self.scores.keys()
             .map(|u| {
-                match self.player_words.get(u) {
-                    Some(words) => format!("{}*{}", u, words.join("*")),
-                    None => u.to_string()
-                }
+                let words = self.player_words.get(u).into_iter().flatten().map(|w| w.word.clone());
+                (u.clone(), words.collect())
             })
-            .collect::<Vec<String>>()
-            .join("*")
+            .collect()
     }
 
-    /// Soummision du mot `word`, de trajectoire `trajectory` par l'utilisateur `user`.
-    pub fn submit_word(&mut self, user: &str, word: &str, trajectory: &str)
-        -> Result<bool, ServerError>
-    {
-        let t = trajectory_of_string(trajectory)?;
+    /// Renvoie la trajectoire `trajectory` si elle est valide et forme le mot `word` sur la grille.
+    pub fn check_trajectory(&self, word: &str, trajectory: &str) -> Result<Vec<Cell>, ServerError> {
+        let t = trajectory_of_string(trajectory, self.size)?;
 
         if self.word_of_trajectory(&t) != word {
             return Err(ServerError::no_match(trajectory, word))
         }
 
+        if word.chars().count() < 3 {
+            return Err(ServerError::bad_trajectory(trajectory))
+        }
+
+        Ok(t)
+    }
+
+    /// Soummision du mot `word`, de trajectoire `trajectory` par l'utilisateur `user`.
+    pub fn submit_word(&mut self, user: &str, word: &str, trajectory: &str)
+        -> Result<bool, ServerError>
+    {
+        let t = self.check_trajectory(word, trajectory)?;
+
         if ! self.scores.contains_key(user) {
             return Err(ServerError::non_existing_user(user))
         }
 
-        if self.played.contains(word) {
+        self.record_word(user, PlayedWord { word: word.to_string(), trajectory: t })
+    }
+
+    /// Attribue le mot `word` au joueur `user`, sauf s'il a déjà été joué lors de ce tour.
+    fn record_word(&mut self, user: &str, word: PlayedWord) -> Result<bool, ServerError> {
+        if self.played.contains(&word.word) {
             if self.immediate {
-                return Err(ServerError::already_played(word, true))
+                return Err(ServerError::already_played(&word.word, true))
             } else {
-                self.invalid_words.insert(word.to_string());
-                return Err(ServerError::already_played(word, false))
+                self.invalid_words.insert(word.word.clone());
+                return Err(ServerError::already_played(&word.word, false))
             }
         }
 
-        self.player_words.entry(user.to_string()).or_insert(vec![])
-            .push(word.to_string());
-        self.played.insert(word.to_string());
+        self.played.insert(word.word.clone());
+        self.player_words.entry(user.to_string()).or_default().push(word);
 
         Ok(self.immediate)
     }
 
-    /// Renvoie le mot correspondant à la trajectoire `trajectory`.
-    fn word_of_trajectory(&self, trajectory: &[(char, usize)]) -> String {
-        trajectory.iter() .map(|&(line, col)| {
-                let idx = (4 * index_of_letter(line)) + (col - 1);
-                self.grid[idx]
+    /// Renvoie un indice sur un mot de la grille qu'aucun joueur n'a encore trouvé,
+    /// et retire au joueur `user` le coût de l'indice.
+    pub fn hint(&mut self, user: &str) -> Result<Hint, ServerError> {
+        if ! self.scores.contains_key(user) {
+            return Err(ServerError::non_existing_user(user))
+        }
+
+        let played = &self.played;
+        let hint = self.hints.entry(user.to_string()).or_default()
+            .next_hint(&self.solutions, |w| played.contains(w))
+            .ok_or(ServerError::no_hint(user))?;
+        *self.penalties.entry(user.to_string()).or_insert(0) += self.hint_penalty;
+        Ok(hint)
+    }
+
+    /// Enregistre le mot `word` de trajectoire `trajectory`, soumis par l'utilisateur `user`
+    /// et refusé par le dictionnaire, afin qu'il puisse le contester pendant la pause.
+    /// Le mot est ignoré si la trajectoire ne permet pas de le former.
+    pub fn reject_word(&mut self, user: &str, word: &str, trajectory: &str) {
+        let t = match trajectory_of_string(trajectory, self.size) {
+            Ok(t) => t,
+            Err(_) => return,
+        };
+        if ! self.scores.contains_key(user) || word.chars().count() < 3 || self.word_of_trajectory(&t) != word {
+            return
+        }
+        let words = self.rejected_words.entry(user.to_string()).or_default();
+        if ! words.iter().any(|w| w.word == word) {
+            words.push(PlayedWord { word: word.to_string(), trajectory: t });
+        }
+    }
+
+    /// Ouvre la contestation du mot `word` par l'utilisateur `user`: un mot qu'il a soumis et
+    /// que le dictionnaire a refusé ou, si c'est permis, un mot accepté d'un adversaire.
+    /// Renvoie le message annonçant la contestation aux joueurs.
+    pub fn challenge(&mut self, user: &str, word: &str) -> Result<ServerMessage, ServerError> {
+        if ! self.scores.contains_key(user) {
+            return Err(ServerError::non_existing_user(user))
+        }
+        if self.challenged.contains(&(user.to_string(), word.to_string())) {
+            return Err(ServerError::already_challenged(user, word))
+        }
+
+        let rejected = self.rejected_words.get(user).is_some_and(|ws| ws.iter().any(|w| w.word == word));
+        let owner = self.player_words.iter()
+            .find(|&(u, ws)| u != user && ws.iter().any(|w| w.word == word))
+            .map(|(u, _)| u.to_string())
+            .filter(|_| self.opponent_challenges && ! self.invalid_words.contains(word));
+        let (kind, owner) = if rejected {
+            (ChallengeKind::Rejected, None)
+        } else if let Some(owner) = owner {
+            (ChallengeKind::Accepted { owner: owner.clone() }, Some(owner))
+        } else {
+            return Err(ServerError::cannot_challenge(user, word))
+        };
+
+        let voters = self.scores.keys()
+            .filter(|&u| u != user && Some(u) != owner.as_ref())
+            .cloned()
+            .collect();
+        let msg = ServerMessage::Challenge {
+            challenger: user.to_string(),
+            word: word.to_string(),
+            kind: kind.clone(),
+        };
+        self.challenged.insert((user.to_string(), word.to_string()));
+        self.challenges.push(Challenge::new(user, word, kind, voters));
+        Ok(msg)
+    }
+
+    /// Enregistre le vote de l'utilisateur `user` sur la contestation du mot `word` par `challenger`.
+    /// Renvoie le verdict s'il est désormais certain, la contestation étant alors close.
+    pub fn vote(&mut self, user: &str, challenger: &str, word: &str, approve: bool)
+        -> Result<Option<Verdict>, ServerError>
+    {
+        let idx = self.challenges.iter()
+            .position(|c| c.challenger == challenger && c.word == word)
+            .ok_or_else(|| ServerError::no_challenge(challenger, word))?;
+        self.challenges[idx].vote(user, approve)?;
+        let verdict = self.challenges[idx].verdict();
+        if let Some(verdict) = verdict {
+            let challenge = self.challenges.remove(idx);
+            self.apply_verdict(&challenge, verdict);
+        }
+        Ok(verdict)
+    }
+
+    /// Clôt les contestations en attente à la fin de la pause et renvoie, pour chacune,
+    /// le joueur l'ayant ouverte, le mot contesté et le verdict.
+    pub fn close_challenges(&mut self) -> Vec<(String, String, Verdict)> {
+        let challenges = self.challenges.drain(..).collect::<Vec<Challenge>>();
+        challenges.into_iter()
+            .map(|challenge| {
+                let verdict = challenge.final_verdict();
+                self.apply_verdict(&challenge, verdict);
+                (challenge.challenger, challenge.word, verdict)
             })
+            .collect()
+    }
+
+    /// Applique le verdict `verdict` de la contestation `challenge` aux mots du tour:
+    /// un mot refusé rapporte ses points au joueur, sauf s'il a entre-temps été joué par un
+    /// autre, et un mot accepté est annulé.
+    fn apply_verdict(&mut self, challenge: &Challenge, verdict: Verdict) {
+        if verdict == Verdict::Dismissed {
+            return
+        }
+        match challenge.kind {
+            ChallengeKind::Rejected => {
+                let words = self.rejected_words.entry(challenge.challenger.clone()).or_default();
+                if let Some(idx) = words.iter().position(|w| w.word == challenge.word) {
+                    let word = words.remove(idx);
+                    // Le mot est traité comme s'il venait d'être soumis: un doublon n'est pas attribué.
+                    let _ = self.record_word(&challenge.challenger, word);
+                }
+            },
+            ChallengeKind::Accepted { .. } => {
+                self.invalid_words.insert(challenge.word.clone());
+            },
+        }
+    }
+
+    /// Renvoie le message annonçant le verdict `verdict` sur la contestation du mot `word`
+    /// par `challenger`, suivi des scores.
+    pub fn verdict_message(&self, challenger: &str, word: &str, verdict: Verdict) -> ServerMessage {
+        ServerMessage::Verdict {
+            challenger: challenger.to_string(),
+            word: word.to_string(),
+            verdict,
+            scores: self.scores(),
+        }
+    }
+
+    /// Renvoie le mot correspondant à la trajectoire `trajectory`.
+    fn word_of_trajectory(&self, trajectory: &[Cell]) -> String {
+        trajectory.iter()
+            .map(|cell| self.grid[cell.index(self.size)].as_str())
             .collect::<String>().to_lowercase()
     }
 
     /// Mise à jour du plateau de jeu après un tour.
-    pub fn new_turn(&mut self) {
+    /// Renvoie les statistiques des grilles aléatoires rejetées.
+    pub fn new_turn(&mut self, dict: &dyn Dict) -> Vec<GridStats> {
         self.update_users_scores();
-        self.update_grid();
+        let rejected = self.update_grid(dict);
         self.player_words.clear();
+        self.hints.clear();
+        self.penalties.clear();
         self.played.clear();
         self.invalid_words.clear();
+        self.clear_challenges();
         self.turn += 1;
+        rejected
+    }
+
+    fn clear_challenges(&mut self) {
+        self.rejected_words.clear();
+        self.challenges.clear();
+        self.challenged.clear();
+    }
+
+    /// Renvoie les mots ayant rapporté des points lors du tour courant, du plus au moins rentable.
+    pub fn scored_words(&self) -> Vec<String> {
+        let mut words = self.player_words.values()
+            .flatten()
+            .filter(|w| ! self.invalid_words.contains(&w.word))
+            .map(|w| (self.bonus.word_score(&*self.scoring, &self.grid, &w.trajectory), w.word.clone()))
+            .collect::<Vec<(u32, String)>>();
+        words.sort_by(|(s1, w1), (s2, w2)| s2.cmp(s1).then_with(|| w1.cmp(w2)));
+        words.into_iter().map(|(_, w)| w).collect()
+    }
+
+    pub fn turn_scores_message(&self) -> ServerMessage {
+        ServerMessage::TurnWords { words: self.words(), scores: self.scores() }
     }
 
-    pub fn turn_scores(&mut self) -> String {
-        format!("BILANMOTS/{}/{}/\n", self.words_str(), self.scores_str())
+    /// Renvoie le message listant les meilleurs mots de la grille qu'aucun joueur n'a trouvés,
+    /// suivi du pourcentage des points de la grille remportés par les joueurs.
+    pub fn missed_words_message(&self) -> ServerMessage {
+        let missed = self.solutions.iter()
+            .filter(|s| ! self.played.contains(&s.word))
+            .take(MAX_MISSED_WORDS)
+            .map(|s| s.word.clone())
+            .collect();
+        ServerMessage::MissedWords { words: missed, percentage: self.captured_percentage() }
+    }
+
+    /// Renvoie le pourcentage des points de la grille courante remportés par les joueurs,
+    /// sans compter les mots annulés ni les cases bonus.
+    fn captured_percentage(&self) -> u32 {
+        let total = self.solutions.iter().map(|s| s.score).sum::<u32>();
+        let captured = self.solutions.iter()
+            .filter(|s| self.played.contains(&s.word) && ! self.invalid_words.contains(&s.word))
+            .map(|s| s.score)
+            .sum::<u32>();
+        (captured * 100).checked_div(total).unwrap_or(100)
     }
 
     /// Ajoute les points du tour courant au score de chaque joueur.
     fn update_users_scores(&mut self) {
-        let mut scores = HashMap::new();
-        for (user, score) in self.scores.iter() {
-            let s = score + self.turn_score(user);
-            scores.insert(user.to_string(), s);
-        }
+        let scores = self.scores.keys()
+            .map(|user| (user.to_string(), self.user_score(user)))
+            .collect();
         self.scores = scores;
     }
 
-    /// Renvoie le score du joueur `user`.
+    /// Renvoie le score du joueur `user`, pénalités des indices déduites.
     fn user_score(&self, user: &str) -> u32 {
-        match self.scores.get(user) {
-            Some(score) => score + self.turn_score(user),
-            None => self.turn_score(user)
-        }
+        let score = self.scores.get(user).cloned().unwrap_or(0) + self.turn_score(user);
+        score.saturating_sub(self.penalties.get(user).cloned().unwrap_or(0))
     }
 
     /// Renvoie le nombre de points gagnés par le joueur `user` lors du tour courant.
     fn turn_score(&self, user: &str) -> u32 {
         self.player_words.get(user).map_or(0, |words| {
             words.iter()
-                .filter(|&w| ! self.invalid_words.contains(w))
-                .map(|w| word_score(w))
+                .filter(|&w| ! self.invalid_words.contains(&w.word))
+                .map(|w| self.bonus.word_score(&*self.scoring, &self.grid, &w.trajectory))
                 .sum()
         })
     }
@@ -193,23 +560,115 @@ impl Board {
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use super::super::super::{dict::TrieDict, protocol};
 
     #[test]
     fn update_grid() {
-        let mut board = Board::new(true, vec!["BBBBBBBBBBBBBBBB".to_string(),
-                                              "CCCCCCCCCCCCCCCC".to_string()]);
-        board.update_grid();
-        assert_eq!(['B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B'], board.grid);
-        board.update_grid();
-        assert_eq!(['C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C', 'C'], board.grid);
-        board.update_grid();
-        assert_eq!(['B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B', 'B'], board.grid);
+        let dict = create_test_dict();
+        let catalogue = create_test_catalogue("[grille]\ngrille: BBBBBBBBBBBBBBBB\n\
+                                               [grille]\ngrille: CCCCCCCCCCCCCCCC", 4);
+        let mut board = Board::new(true).with_catalogue(catalogue);
+        board.update_grid(&dict);
+        assert_eq!(board.grid_str(), "BBBBBBBBBBBBBBBB");
+        board.update_grid(&dict);
+        assert_eq!(board.grid_str(), "CCCCCCCCCCCCCCCC");
+        board.update_grid(&dict);
+        assert_eq!(board.grid_str(), "BBBBBBBBBBBBBBBB");
+    }
+
+    #[test]
+    fn update_grid_with_size() {
+        let dict = create_test_dict();
+        let catalogue = create_test_catalogue(&format!("[grille]\ngrille: {}", "B".repeat(25)), 5);
+        let mut board = Board::new(true).with_size(5).with_catalogue(catalogue);
+        board.update_grid(&dict);
+        assert_eq!(board.grid_str(), "B".repeat(25));
+        board.update_grid(&dict);
+        assert_eq!(board.grid.len(), 25);
+    }
+
+    #[test]
+    fn update_grid_with_dices() {
+        let dict = create_test_dict();
+        let dices = Dices::of_language("en", 5).unwrap();
+        let mut board = Board::new(true).with_size(5).with_dices(dices);
+        board.update_grid(&dict);
+        assert_eq!(board.grid.len(), 25);
+    }
+
+    #[test]
+    fn seeded_sessions_are_reproducible() {
+        let dict = create_test_dict();
+        let mut board1 = Board::new(true).with_seed(42);
+        let mut board2 = Board::new(true).with_seed(42);
+        let mut grids = vec![];
+        for _ in 0..3 {
+            board1.new_turn(&dict);
+            board2.new_turn(&dict);
+            assert_eq!(board1.grid, board2.grid);
+            assert_eq!(board1.turn_seed, board2.turn_seed);
+            grids.push(board1.grid.clone());
+        }
+
+        board1.reset();
+        assert_eq!(board1.seed(), 42);
+        for grid in grids {
+            board1.new_turn(&dict);
+            assert_eq!(board1.grid, grid);
+        }
+    }
+
+    #[test]
+    fn seeded_sessions_start_with_same_grid() {
+        let dict = create_test_dict();
+        let mut board1 = Board::new(true).with_seed(42);
+        let mut board2 = Board::new(true).with_seed(42);
+        board1.new_turn(&dict);
+        board1.reset();
+        for _ in 0..3 {
+            board2.new_turn(&dict);
+        }
+        board2.reset();
+
+        board1.new_turn(&dict);
+        board2.new_turn(&dict);
+        assert_eq!(board1.grid, board2.grid);
+
+        let mut board3 = Board::new(true).with_seed(42);
+        board3.new_turn(&dict);
+        assert_eq!(board1.grid, board3.grid);
+    }
+
+    #[test]
+    fn grid_can_be_regenerated_from_turn_seed() {
+        let dict = create_test_dict();
+        let mut board = Board::new(true);
+        board.new_turn(&dict);
+        assert_eq!(board.grid, Dices::new(4).roll(board.turn_seed));
+    }
+
+    #[test]
+    fn unseeded_sessions_differ() {
+        let mut board = Board::new(true);
+        let seed = board.seed();
+        board.reset();
+        assert_ne!(board.seed(), seed);
+    }
+
+    #[test]
+    fn submit_word_big_grid() {
+        let mut board = Board::new(true).with_size(5);
+        board.grid = grid_of_string("LIDAXREJUXLTNEXATNGXXXXXX", 5).unwrap();
+        board.add_user("user1");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.user_score("user1"), 5);
+        assert!(board.submit_word("user1", "xxx", "E3E4E5").is_ok());
     }
 
     #[test]
     fn word_of_trajectory() {
         let board = create_test_board();
-        let trajectory = vec![('C', 2), ('B', 1), ('A', 2), ('A', 3), ('B', 2), ('C', 3), ('D', 2)];
+        let trajectory = trajectory_of_string("C2B1A2A3B2C3D2", 4).unwrap();
         assert_eq!(board.word_of_trajectory(&trajectory), "trident");
     }
 
@@ -243,7 +702,10 @@ pub mod test {
         let mut board = create_test_board();
         board.add_user("user1");
         board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
-        let expected = vec!["trident".to_string()];
+        let expected = vec![PlayedWord {
+            word: "trident".to_string(),
+            trajectory: trajectory_of_string("C2B1A2A3B2C3D2", 4).unwrap(),
+        }];
         assert_eq!(board.player_words.get("user1").unwrap(), &expected);
     }
 
@@ -265,20 +727,274 @@ pub mod test {
     }
 
     #[test]
-    fn welcome_str() {
+    fn submit_word_multi_letter_tile() {
+        let mut board = create_test_board();
+        board.grid = grid_of_string("LIDA[QU]EJULTNEATNG", 4).unwrap();
+        board.add_user("user1");
+        board.submit_word("user1", "quel", "B1B2C1").unwrap();
+        assert_eq!(board.user_score("user1"), 1);
+        match board.submit_word("user1", "que", "B1B2") {
+            Ok(_) => (),
+            Err(e) => panic!("{}", e)
+        }
+        match board.submit_word("user1", "qu", "B1") {
+            Err(ServerError::BadTrajectory {..}) => (),
+            _ => panic!("A one square trajectory is invalid !")
+        }
+    }
+
+    #[test]
+    fn short_word_is_rejected() {
+        let mut board = create_test_board();
+        board.add_user("user1");
+        match board.submit_word("user1", "il", "A2A1") {
+            Err(ServerError::BadTrajectory {..}) => (),
+            _ => panic!("\"il\" is too short !")
+        }
+    }
+
+    #[test]
+    fn welcome_message() {
+        let mut board = create_test_board();
+        board.add_user("user1");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.welcome_message().encode(), "BIENVENUE/LIDAREJULTNEATNG/1*user1*5/\n")
+    }
+
+    #[test]
+    fn missed_words_message() {
+        let dict = TrieDict::from_words(["trident", "ile", "lire", "dent", "tenue"].iter().map(|w| w.to_string()));
+        let mut board = create_test_board();
+        board.solutions = solver::solve(&board.grid, 4, &dict, &Classic);
+        board.add_user("user1");
+        assert_eq!(board.missed_words_message().encode(), "MANQUES/trident*tenue*dent*ile*lire/0/\n");
+        board.submit_word("user1", "ile", "A2A1B2").unwrap();
+        assert_eq!(board.missed_words_message().encode(), "MANQUES/trident*tenue*dent*lire/10/\n");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.missed_words_message().encode(), "MANQUES/tenue*dent*lire/60/\n");
+        board.solutions.clear();
+        assert_eq!(board.missed_words_message().encode(), "MANQUES//100/\n");
+    }
+
+    #[test]
+    fn missed_words_ignore_cancelled_words() {
+        let dict = TrieDict::from_words(["trident", "ile"].iter().map(|w| w.to_string()));
+        let mut board = create_test_board();
+        board.immediate = false;
+        board.solutions = solver::solve(&board.grid, 4, &dict, &Classic);
+        board.add_user("user1");
+        board.add_user("user2");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert!(board.submit_word("user2", "trident", "C2B1A2A3B2C3D2").is_err());
+        assert_eq!(board.missed_words_message().encode(), "MANQUES/ile/0/\n");
+    }
+
+    #[test]
+    fn missed_words_are_limited() {
+        let mut board = create_test_board();
+        board.solutions = (0..20)
+            .map(|i| Solution { word: format!("mot{}", i), trajectory: vec![], score: 1 })
+            .collect();
+        let msg = board.missed_words_message().encode();
+        assert_eq!(msg.split('*').count(), MAX_MISSED_WORDS);
+        assert!(msg.starts_with("MANQUES/mot0*mot1*"));
+    }
+
+    #[test]
+    fn scored_words() {
+        let mut board = create_test_board();
+        board.immediate = false;
+        board.add_user("user1");
+        board.add_user("user2");
+        board.add_user("user3");
+        board.submit_word("user1", "ile", "A2A1B2").unwrap();
+        board.submit_word("user2", "trident", "C2B1A2A3B2C3D2").unwrap();
+        board.submit_word("user1", "lie", "A1A2B2").unwrap();
+        board.submit_word("user2", "tenu", "C2B2C3B4").unwrap();
+        assert!(board.submit_word("user3", "tenu", "C2B2C3B4").is_err());
+        assert_eq!(board.scored_words(), vec!["trident", "ile", "lie"]);
+    }
+
+    #[test]
+    fn hint_costs_points() {
+        let dict = create_test_dict();
+        let mut board = create_test_board().with_hint_penalty(2);
+        board.solutions = solver::solve(&board.grid, 4, &dict, &Classic);
+        board.add_user("user1");
+        board.submit_word("user1", "ile", "A2A1B2").unwrap();
+        assert_eq!(board.hint("user1").unwrap(), Hint::FirstLetter('t'));
+        assert_eq!(board.user_score("user1"), 0);
+        assert_eq!(board.hint("user1").unwrap(), Hint::Length(7));
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.user_score("user1"), 2);
+        board.new_turn(&dict);
+        assert_eq!(board.scores.get("user1").unwrap(), &2);
+        assert_eq!(board.penalties, HashMap::new());
+    }
+
+    #[test]
+    fn hint_errors() {
+        let dict = create_test_dict();
         let mut board = create_test_board();
+        board.solutions = solver::solve(&board.grid, 4, &dict, &Classic);
+        match board.hint("user1") {
+            Err(ServerError::NonExistingUser {..}) => (),
+            _ => panic!("user1 doesn't exist !")
+        }
         board.add_user("user1");
+        board.submit_word("user1", "ile", "A2A1B2").unwrap();
         board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
-        assert_eq!(board.welcome_str(), "BIENVENUE/LIDAREJULTNEATNG/1*user1*5/\n")
+        match board.hint("user1") {
+            Err(ServerError::NoHint {..}) => (),
+            _ => panic!("Every word has been found !")
+        }
+        assert_eq!(board.user_score("user1"), 6);
+    }
+
+    fn create_challenge_board(opponent_challenges: bool) -> Board {
+        let mut board = create_test_board().with_opponent_challenges(opponent_challenges);
+        board.immediate = false;
+        for user in &["user1", "user2", "user3"] {
+            board.add_user(user);
+        }
+        board
+    }
+
+    #[test]
+    fn challenge_rejected_word() {
+        let mut board = create_challenge_board(false);
+        board.reject_word("user1", "trident", "C2B1A2A3B2C3D2");
+        board.reject_word("user1", "ile", "A1A2B2");
+        assert_eq!(board.challenge("user1", "trident").unwrap().encode(), "CONTESTATION/user1/trident/AJOUT/\n");
+        assert_eq!(board.vote("user2", "user1", "trident", true).unwrap(), None);
+        assert_eq!(board.vote("user3", "user1", "trident", true).unwrap(), Some(Verdict::Upheld));
+        assert_eq!(board.user_score("user1"), 5);
+        assert!(board.played.contains("trident"));
+        match board.challenge("user1", "ile") {
+            Err(ServerError::CannotChallenge {..}) => (),
+            _ => panic!("The trajectory of ile was invalid !")
+        }
+    }
+
+    #[test]
+    fn challenge_errors() {
+        let mut board = create_challenge_board(false);
+        board.reject_word("user1", "trident", "C2B1A2A3B2C3D2");
+        board.submit_word("user2", "ile", "A2A1B2").unwrap();
+        match board.challenge("user2", "trident") {
+            Err(ServerError::CannotChallenge {..}) => (),
+            _ => panic!("user2 did not submit trident !")
+        }
+        match board.challenge("user1", "ile") {
+            Err(ServerError::CannotChallenge {..}) => (),
+            _ => panic!("Opponent challenges are disabled !")
+        }
+        board.challenge("user1", "trident").unwrap();
+        match board.challenge("user1", "trident") {
+            Err(ServerError::AlreadyChallenged {..}) => (),
+            _ => panic!("trident has already been challenged !")
+        }
+        match board.vote("user2", "user1", "ile", true) {
+            Err(ServerError::NoChallenge {..}) => (),
+            _ => panic!("ile has not been challenged !")
+        }
+    }
+
+    #[test]
+    fn same_word_challenged_by_two_players() {
+        let mut board = create_challenge_board(false);
+        board.reject_word("user1", "trident", "C2B1A2A3B2C3D2");
+        board.reject_word("user2", "trident", "C2B1A2A3B2C3D2");
+        board.challenge("user1", "trident").unwrap();
+        board.challenge("user2", "trident").unwrap();
+        assert_eq!(board.vote("user3", "user1", "trident", true).unwrap(), None);
+        assert_eq!(board.vote("user2", "user1", "trident", true).unwrap(), Some(Verdict::Upheld));
+        assert_eq!(board.user_score("user1"), 5);
+
+        // Le second verdict est traité comme une nouvelle soumission du mot: un doublon.
+        board.vote("user1", "user2", "trident", true).unwrap();
+        assert_eq!(board.vote("user3", "user2", "trident", true).unwrap(), Some(Verdict::Upheld));
+        assert!(board.invalid_words.contains("trident"));
+        assert_eq!(board.user_score("user1"), 0);
+        assert_eq!(board.user_score("user2"), 0);
+    }
+
+    #[test]
+    fn challenge_opponent_word() {
+        let mut board = create_challenge_board(true);
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.challenge("user2", "trident").unwrap().encode(), "CONTESTATION/user2/trident/ANNULATION/\n");
+        match board.vote("user1", "user2", "trident", false) {
+            Err(ServerError::NotAVoter {..}) => (),
+            _ => panic!("user1 played the challenged word !")
+        }
+        assert_eq!(board.vote("user3", "user2", "trident", true).unwrap(), Some(Verdict::Upheld));
+        assert_eq!(board.user_score("user1"), 0);
+    }
+
+    #[test]
+    fn pending_challenges_are_dismissed() {
+        let dict = create_test_dict();
+        let mut board = create_challenge_board(false);
+        board.reject_word("user1", "trident", "C2B1A2A3B2C3D2");
+        board.challenge("user1", "trident").unwrap();
+        board.vote("user2", "user1", "trident", true).unwrap();
+        let verdicts = board.close_challenges();
+        assert_eq!(verdicts, vec![("user1".to_string(), "trident".to_string(), Verdict::Dismissed)]);
+        assert!(board.challenges.is_empty());
+        let verdict = board.verdict_message("user1", "trident", Verdict::Dismissed).encode();
+        assert_eq!(verdict.split('/').nth(3), Some("REJETEE"));
+        board.new_turn(&dict);
+        assert_eq!(board.scores.get("user1").unwrap(), &0);
+        assert_eq!(board.rejected_words, HashMap::new());
+    }
+
+    #[test]
+    fn bonus_in_messages() {
+        let mut board = create_test_board();
+        assert_eq!(board.turn_message().encode(), "TOUR/LIDAREJULTNEATNG/\n");
+        board.bonus = BonusLayout::parse("..L..m..........", 4).unwrap();
+        board.add_user("user1");
+        assert_eq!(board.turn_message().encode(), "TOUR/LIDAREJULTNEATNG/..L..m........../\n");
+        assert_eq!(board.welcome_message().encode(), "BIENVENUE/LIDAREJULTNEATNG/1*user1*0/..L..m........../\n");
+    }
+
+    #[test]
+    fn bonus_uses_trajectory() {
+        let dict = create_test_dict();
+        let mut board = create_test_board();
+        board.bonus = BonusLayout::parse("..L..m..........", 4).unwrap();
+        board.add_user("user1");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        assert_eq!(board.user_score("user1"), (5 + 2) * 2);
+        board.new_turn(&dict);
+        assert_eq!(board.scores.get("user1").unwrap(), &14);
+    }
+
+    #[test]
+    fn bonus_layouts() {
+        let dict = create_test_dict();
+        let mut board = Board::new(true).with_seed(42).with_bonus(3);
+        board.new_turn(&dict);
+        assert_eq!(board.bonus, BonusLayout::random(board.turn_seed, 4, 3));
+
+        let catalogue = create_test_catalogue("[grille]\ngraine: 1\nbonus: M...............\n\
+                                               [grille]\ngraine: 2", 4);
+        let mut board = Board::new(true).with_bonus(3).with_catalogue(catalogue);
+        board.new_turn(&dict);
+        assert_eq!(board.bonus.to_string(), "M...............");
+        board.new_turn(&dict);
+        assert_eq!(board.bonus, BonusLayout::random(2, 4, 3));
     }
 
     #[test]
     fn new_turn() {
+        let dict = create_test_dict();
         let mut board = create_test_board();
-        let old_grid = board.grid;
+        let old_grid = board.grid.clone();
         let old_turn = board.turn;
 
-        board.new_turn();
+        board.new_turn(&dict);
 
         assert_eq!(board.turn, old_turn + 1);
         assert_eq!(board.player_words, HashMap::new());
@@ -289,33 +1005,123 @@ pub mod test {
 
     #[test]
     fn scores_updated_after_new_turn() {
+        let dict = create_test_dict();
         let mut board = create_test_board();
         board.add_user("user1");
         board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
-        assert_eq!(board.scores.get("user1").unwrap(), &(0 as u32));
-        board.new_turn();
-        assert_eq!(board.scores.get("user1").unwrap(), &(5 as u32));
+        assert_eq!(board.scores.get("user1").unwrap(), &0);
+        board.new_turn(&dict);
+        assert_eq!(board.scores.get("user1").unwrap(), &5);
+    }
+
+    #[test]
+    fn scores_use_scoring_rule() {
+        let dict = create_test_dict();
+        let mut board = create_test_board().with_scoring(Box::new(Scrabble));
+        board.add_user("user1");
+        board.submit_word("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        board.new_turn(&dict);
+        assert_eq!(board.scores.get("user1").unwrap(), &8);
     }
 
     #[test]
     fn reset() {
         let mut board = create_test_board();
-        let old_grid = board.grid;
+        let old_grid = board.grid.clone();
 
         board.reset();
 
         assert_eq!(board.turn, 1);
         assert_eq!(board.player_words, HashMap::new());
         assert_eq!(board.played, HashSet::new());
-        assert_ne!(board.grid, old_grid);
+        assert_eq!(board.grid, old_grid);
+    }
+
+    #[test]
+    fn rejected_grids_are_rolled_again() {
+        let dict = TrieDict::from_words(vec!["ile".to_string(), "lie".to_string()]);
+        let filter = GridFilter { min_words: 1, ..GridFilter::default() };
+        let mut board = Board::new(true).with_seed(42).with_filter(filter);
+        let rejected = board.new_turn(&dict);
+        assert!(! rejected.is_empty());
+        assert!(rejected.iter().all(|stats| stats.nb_words == 0));
+        assert!(board.stats().nb_words >= 1);
+        assert_eq!(board.grid, Dices::new(4).roll(board.turn_seed));
+    }
+
+    #[test]
+    fn rejections_are_bounded() {
+        let dict = create_test_dict();
+        let filter = GridFilter { min_words: 1000, max_rejections: 3, ..GridFilter::default() };
+        let mut board = Board::new(true).with_filter(filter);
+        assert_eq!(board.new_turn(&dict).len(), 3);
+    }
+
+    #[test]
+    fn fixed_grids_are_never_rejected() {
+        let dict = create_test_dict();
+        let filter = GridFilter { min_words: 1000, ..GridFilter::default() };
+        let catalogue = create_test_catalogue("[grille]\ngrille: LIDAREJULTNEATNG", 4);
+        let mut board = Board::new(true).with_filter(filter).with_catalogue(catalogue);
+        assert!(board.new_turn(&dict).is_empty());
+        assert_eq!(board.stats().nb_words, 2);
+        assert_eq!(board.solutions[0].word, "trident");
+    }
+
+    #[test]
+    fn catalogue_metadata() {
+        let dict = create_test_dict();
+        let catalogue = create_test_catalogue("[grille]\nnom: Trident\ngrille: LIDAREJULTNEATNG\n\
+                                               [grille]\ngraine: 42", 4);
+        let mut board = Board::new(true).with_catalogue(catalogue);
+        board.new_turn(&dict);
+        assert_eq!(board.description(), Some("<Trident>"));
+        board.new_turn(&dict);
+        assert_eq!(board.description(), Some("ligne 4"));
+        assert_eq!(board.turn_seed, 42);
+        assert_eq!(board.grid, Dices::new(4).roll(42));
+    }
+
+    #[test]
+    fn reset_rewinds_catalogue() {
+        let dict = create_test_dict();
+        let catalogue = create_test_catalogue("[grille]\ngrille: BBBBBBBBBBBBBBBB\n\
+                                               [grille]\ngrille: CCCCCCCCCCCCCCCC", 4);
+        let mut board = Board::new(true).with_catalogue(catalogue);
+        board.new_turn(&dict);
+        board.reset();
+        board.new_turn(&dict);
+        assert_eq!(board.grid_str(), "BBBBBBBBBBBBBBBB");
+    }
+
+    #[test]
+    fn hostile_usernames_are_escaped() {
+        let mut board = create_test_board();
+        board.add_user("a*b");
+        board.add_user("c/d");
+        board.submit_word("c/d", "lid", "A1A2A3").unwrap();
+        let msg = board.turn_scores_message().encode();
+        let fields = protocol::decode_message(msg.trim_end_matches('\n'));
+        assert_eq!(fields.len(), 4);
+        let mut words = protocol::decode_list(fields[1]);
+        words.sort();
+        assert_eq!(words, vec!["a*b", "c/d", "lid"]);
+        let mut scores = protocol::decode_list(fields[2]);
+        scores.sort();
+        assert_eq!(scores, vec!["0", "1", "a*b", "c/d"]);
+    }
+
+    fn create_test_catalogue(content: &str, size: usize) -> Catalogue {
+        Catalogue::parse(content, size, &Dices::new(size)).unwrap()
+    }
+
+    fn create_test_dict() -> TrieDict {
+        TrieDict::from_dictionary("dico_test.txt")
     }
 
     pub fn create_test_board() -> Board {
-        let mut board = Board::new(true, vec![]);
-        board.grid = ['L', 'I', 'D', 'A',
-                     'R', 'E', 'J', 'U',
-                     'L', 'T', 'N', 'E',
-                     'A', 'T', 'N', 'G' ];
+        let mut board = Board::new(true);
+        board.grid = grid_of_string("LIDAREJULTNEATNG", 4).unwrap();
         board.turn = 1;
         board
     }
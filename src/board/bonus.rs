@@ -0,0 +1,207 @@
+use super::{Cell, ScoringRule};
+
+use std::fmt;
+
+use rand::{Rng, SeedableRng, Isaac64Rng};
+
+/// Case bonus multipliant les points d'une lettre ou d'un mot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    None,
+    DoubleLetter,
+    TripleLetter,
+    DoubleWord,
+    TripleWord,
+}
+
+/// Bonus tirés pour les dispositions aléatoires: les bonus de lettre sont plus fréquents.
+const RANDOM_BONUSES: [Bonus; 6] = [
+    Bonus::DoubleLetter, Bonus::DoubleLetter, Bonus::DoubleLetter,
+    Bonus::TripleLetter, Bonus::DoubleWord, Bonus::TripleWord,
+];
+
+impl Bonus {
+    /// Lit un bonus: `.` aucun, `l` lettre compte double, `L` lettre compte triple,
+    /// `m` mot compte double, `M` mot compte triple.
+    fn of_char(c: char) -> Option<Bonus> {
+        match c {
+            '.' => Some(Bonus::None),
+            'l' => Some(Bonus::DoubleLetter),
+            'L' => Some(Bonus::TripleLetter),
+            'm' => Some(Bonus::DoubleWord),
+            'M' => Some(Bonus::TripleWord),
+            _ => None
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Bonus::None => '.',
+            Bonus::DoubleLetter => 'l',
+            Bonus::TripleLetter => 'L',
+            Bonus::DoubleWord => 'm',
+            Bonus::TripleWord => 'M',
+        }
+    }
+
+    fn letter_multiplier(self) -> u32 {
+        match self {
+            Bonus::DoubleLetter => 2,
+            Bonus::TripleLetter => 3,
+            _ => 1
+        }
+    }
+
+    fn word_multiplier(self) -> u32 {
+        match self {
+            Bonus::DoubleWord => 2,
+            Bonus::TripleWord => 3,
+            _ => 1
+        }
+    }
+}
+
+/// Disposition des cases bonus d'une grille, ligne par ligne.
+/// Elle s'écrit avec un caractère par case, par exemple `l..M....m.....L.` pour une grille 4x4.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BonusLayout {
+    size: usize,
+    cells: Vec<Bonus>,
+}
+
+impl BonusLayout {
+    /// Disposition sans aucune case bonus.
+    pub fn empty(size: usize) -> BonusLayout {
+        BonusLayout { size, cells: vec![Bonus::None; size * size] }
+    }
+
+    /// Disposition de `count` cases bonus placées au hasard.
+    /// Une même graine `seed` produit toujours la même disposition.
+    pub fn random(seed: u64, size: usize, count: usize) -> BonusLayout {
+        let mut rng = Isaac64Rng::from_seed(&[seed, 0xb0b0][..]);
+        let mut indexes = (0..size * size).collect::<Vec<usize>>();
+        rng.shuffle(&mut indexes);
+
+        let mut layout = BonusLayout::empty(size);
+        for idx in indexes.into_iter().take(count) {
+            layout.cells[idx] = *rng.choose(&RANDOM_BONUSES).unwrap();
+        }
+        layout
+    }
+
+    /// Lit la disposition `s` d'une grille de `size` x `size` cases.
+    pub fn parse(s: &str, size: usize) -> Option<BonusLayout> {
+        let cells = s.chars()
+            .map(Bonus::of_char)
+            .collect::<Option<Vec<Bonus>>>()?;
+        if cells.len() == size * size {
+            Some(BonusLayout { size, cells })
+        } else {
+            None
+        }
+    }
+
+    /// Renvoie true si la disposition ne contient aucune case bonus.
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|&b| b == Bonus::None)
+    }
+
+    /// Renvoie le bonus de la case `cell`.
+    pub fn get(&self, cell: Cell) -> Bonus {
+        self.cells[cell.index(self.size)]
+    }
+
+    /// Renvoie les points rapportés par le mot formé sur la grille `grid` en suivant `trajectory`.
+    /// Chaque lettre posée sur une case lettre compte double ou triple rapporte à nouveau sa
+    /// valeur selon `scoring`, puis le total est multiplié par les cases mot compte double ou triple.
+    pub fn word_score(&self, scoring: &dyn ScoringRule, grid: &[String], trajectory: &[Cell]) -> u32 {
+        let tiles = trajectory.iter()
+            .map(|&cell| (grid[cell.index(self.size)].to_lowercase(), self.get(cell)))
+            .collect::<Vec<(String, Bonus)>>();
+        let word = tiles.iter().map(|(tile, _)| tile.as_str()).collect::<String>();
+
+        let letters_bonus = tiles.iter()
+            .map(|(tile, bonus)| {
+                (bonus.letter_multiplier() - 1) * tile.chars().map(|c| scoring.letter_score(c)).sum::<u32>()
+            })
+            .sum::<u32>();
+        let word_multiplier = tiles.iter()
+            .map(|(_, bonus)| bonus.word_multiplier())
+            .product::<u32>();
+
+        (scoring.word_score(&word) + letters_bonus) * word_multiplier
+    }
+}
+
+impl fmt::Display for BonusLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.cells.iter().try_for_each(|b| write!(f, "{}", b.to_char()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::{grid_of_string, trajectory_of_string, Classic, Scrabble, GRID_SIZES};
+
+    fn trident() -> Vec<Cell> {
+        trajectory_of_string("C2B1A2A3B2C3D2", 4).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let layout = BonusLayout::parse("l..M....m.....L.", 4).unwrap();
+        assert_eq!(layout.get(Cell::new(0, 0)), Bonus::DoubleLetter);
+        assert_eq!(layout.get(Cell::new(0, 3)), Bonus::TripleWord);
+        assert_eq!(layout.get(Cell::new(2, 0)), Bonus::DoubleWord);
+        assert_eq!(layout.get(Cell::new(3, 2)), Bonus::TripleLetter);
+        assert_eq!(layout.to_string(), "l..M....m.....L.");
+        assert!(! layout.is_empty());
+        assert!(BonusLayout::empty(4).is_empty());
+    }
+
+    #[test]
+    fn parse_err() {
+        assert_eq!(BonusLayout::parse("l..M....m.....L", 4), None);
+        assert_eq!(BonusLayout::parse("l..M....m.....Lx", 4), None);
+    }
+
+    #[test]
+    fn random_layout() {
+        for &size in GRID_SIZES.iter() {
+            let layout = BonusLayout::random(42, size, 5);
+            assert_eq!(layout.cells.len(), size * size);
+            assert_eq!(layout.cells.iter().filter(|&&b| b != Bonus::None).count(), 5);
+            assert_eq!(layout, BonusLayout::random(42, size, 5));
+        }
+        assert!(BonusLayout::random(42, 4, 0).is_empty());
+        assert_eq!(BonusLayout::random(42, 4, 100).to_string().find('.'), None);
+    }
+
+    #[test]
+    fn word_score_without_bonus() {
+        let grid = grid_of_string("LIDAREJULTNEATNG", 4).unwrap();
+        assert_eq!(BonusLayout::empty(4).word_score(&Classic, &grid, &trident()), 5);
+        assert_eq!(BonusLayout::empty(4).word_score(&Scrabble, &grid, &trident()), 8);
+    }
+
+    #[test]
+    fn word_score_with_bonus() {
+        let grid = grid_of_string("LIDAREJULTNEATNG", 4).unwrap();
+        // Le D de trident (A3) compte triple et le E (B2) fait compter le mot double.
+        let layout = BonusLayout::parse("..L..m..........", 4).unwrap();
+        assert_eq!(layout.word_score(&Scrabble, &grid, &trident()), (8 + 2 * 2) * 2);
+        assert_eq!(layout.word_score(&Classic, &grid, &trident()), (5 + 2) * 2);
+        // Les bonus hors de la trajectoire ne comptent pas.
+        let layout = BonusLayout::parse("...M...........M", 4).unwrap();
+        assert_eq!(layout.word_score(&Scrabble, &grid, &trident()), 8);
+    }
+
+    #[test]
+    fn word_score_multi_letter_tiles() {
+        let grid = grid_of_string("LIDA[QU]EJULTNEATNG", 4).unwrap();
+        let que = trajectory_of_string("B1B2", 4).unwrap();
+        let layout = BonusLayout::parse("....l...........", 4).unwrap();
+        assert_eq!(layout.word_score(&Scrabble, &grid, &que), 10 + 9);
+    }
+}
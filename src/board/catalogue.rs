@@ -0,0 +1,334 @@
+use super::{BonusLayout, Classic, Dices, grid_of_string, split_line, solver};
+use super::super::{
+    dict::Dict,
+    errors::CatalogueError,
+};
+
+use std::{
+    fmt,
+    fs,
+};
+
+use rand::Rng;
+
+/// Grille du catalogue et ses métadonnées.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogueEntry {
+    /// Nom de la grille.
+    pub name: Option<String>,
+    /// Difficulté annoncée de la grille.
+    pub difficulty: Option<String>,
+    /// Graine ayant servi à générer la grille, si elle a été tirée aux dés.
+    pub seed: Option<u64>,
+    /// Cases de la grille.
+    pub grid: Vec<String>,
+    /// Mots devant pouvoir être formés sur la grille.
+    pub solutions: Vec<String>,
+    /// Disposition des cases bonus de la grille, si elle est fixée.
+    pub bonus: Option<BonusLayout>,
+    /// Ligne du fichier où commence la grille.
+    line: usize,
+}
+
+impl CatalogueEntry {
+    fn new(line: usize) -> CatalogueEntry {
+        CatalogueEntry {
+            name: None,
+            difficulty: None,
+            seed: None,
+            grid: vec![],
+            solutions: vec![],
+            bonus: None,
+            line,
+        }
+    }
+}
+
+impl fmt::Display for CatalogueEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(ref name) => write!(f, "<{}>", name)?,
+            None => write!(f, "ligne {}", self.line)?,
+        }
+        if let Some(ref difficulty) = self.difficulty {
+            write!(f, ", difficulté {}", difficulty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ordre dans lequel les grilles du catalogue sont jouées.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    /// Dans l'ordre du fichier.
+    Sequential,
+    /// Dans un ordre aléatoire, tiré à nouveau à chaque passage sur le catalogue.
+    Shuffled,
+}
+
+/// Catalogue des grilles à jouer à la place des grilles aléatoires.
+///
+/// Le fichier contient une section `[grille]` par grille, suivie de lignes `clé: valeur`:
+///
+/// ```text
+/// # Commentaire
+/// [grille]
+/// nom: Trident
+/// difficulte: facile
+/// grille: LIDAREJULTNEATNG
+/// solutions: trident, ile
+/// bonus: l..M....m.....L.
+///
+/// [grille]
+/// graine: 1234
+/// ```
+///
+/// Chaque grille doit avoir une clé `grille` ou une clé `graine`, auquel cas
+/// la grille est tirée avec les dés de la partie. La clé `bonus` fixe la disposition
+/// des cases bonus de la grille (voir `BonusLayout`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catalogue {
+    entries: Vec<CatalogueEntry>,
+    order: Order,
+    /// Indices des grilles restant à jouer avant de repasser sur le catalogue.
+    queue: Vec<usize>,
+}
+
+impl Catalogue {
+    /// Catalogue vide: toutes les grilles seront aléatoires.
+    pub fn new() -> Catalogue {
+        Catalogue { entries: vec![], order: Order::Sequential, queue: vec![] }
+    }
+
+    /// Charge le catalogue `file` pour des grilles de `size` x `size` cases.
+    pub fn from_file(file: &str, size: usize, dices: &Dices) -> Result<Catalogue, CatalogueError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| CatalogueError::unreadable_file(file, &e.to_string()))?;
+        Catalogue::parse(&content, size, dices)
+    }
+
+    /// Définit l'ordre dans lequel les grilles sont jouées.
+    pub fn with_order(mut self, order: Order) -> Catalogue {
+        self.order = order;
+        self
+    }
+
+    /// Lit un catalogue depuis son contenu `content`.
+    pub(crate) fn parse(content: &str, size: usize, dices: &Dices) -> Result<Catalogue, CatalogueError> {
+        let mut entries: Vec<CatalogueEntry> = vec![];
+        let mut keys: Vec<String> = vec![];
+
+        for (idx, line) in content.lines().enumerate() {
+            let line_number = idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            if line == "[grille]" {
+                if let Some(entry) = entries.last_mut() {
+                    check_entry(entry, dices)?;
+                }
+                entries.push(CatalogueEntry::new(line_number));
+                keys.clear();
+                continue
+            }
+
+            let entry = entries.last_mut().ok_or(CatalogueError::MissingHeader { line: line_number })?;
+            let (key, value) = split_line(line).ok_or(CatalogueError::InvalidLine { line: line_number })?;
+            if keys.iter().any(|k| k == key) {
+                return Err(CatalogueError::duplicate_key(line_number, key))
+            }
+            keys.push(key.to_string());
+
+            match key {
+                "nom" => entry.name = Some(value.to_string()),
+                "difficulte" => entry.difficulty = Some(value.to_string()),
+                "graine" => entry.seed = Some(value.parse::<u64>()
+                    .map_err(|_| CatalogueError::invalid_seed(line_number, value))?),
+                "grille" => entry.grid = grid_of_string(value, size)
+                    .ok_or(CatalogueError::invalid_grid(line_number, value, size))?,
+                "solutions" => entry.solutions = value.split(',')
+                    .map(|w| w.trim().to_string())
+                    .filter(|w| ! w.is_empty())
+                    .collect(),
+                "bonus" => entry.bonus = Some(BonusLayout::parse(value, size)
+                    .ok_or(CatalogueError::invalid_bonus(line_number, value, size))?),
+                _ => return Err(CatalogueError::unknown_key(line_number, key)),
+            }
+        }
+
+        match entries.last_mut() {
+            Some(entry) => check_entry(entry, dices)?,
+            None => return Err(CatalogueError::Empty),
+        }
+
+        Ok(Catalogue { entries, order: Order::Sequential, queue: vec![] })
+    }
+
+    /// Vérifie que les solutions annoncées de chaque grille peuvent être formées à l'aide de `dict`.
+    /// Les solutions sont normalisées comme les mots de `dict`.
+    pub fn check_solutions(&self, size: usize, dict: &dyn Dict) -> Result<(), CatalogueError> {
+        for entry in self.entries.iter().filter(|e| ! e.solutions.is_empty()) {
+            let found = solver::solve(&entry.grid, size, dict, &Classic);
+            for word in entry.solutions.iter() {
+                let normalized = dict.normalizer().normalize(word);
+                if ! found.iter().any(|s| normalized.as_ref().is_some_and(|w| s.word == w.as_ref())) {
+                    return Err(CatalogueError::not_a_solution(entry.line, word))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Renvoie la prochaine grille à jouer, ou `None` si le catalogue est vide.
+    pub fn next_entry<R: Rng>(&mut self, rng: &mut R) -> Option<&CatalogueEntry> {
+        if self.entries.is_empty() {
+            return None
+        }
+        if self.queue.is_empty() {
+            self.queue = (0..self.entries.len()).rev().collect();
+            if self.order == Order::Shuffled {
+                rng.shuffle(&mut self.queue);
+            }
+        }
+        let idx = self.queue.pop().unwrap();
+        self.entries.get(idx)
+    }
+
+    /// Reprend le catalogue depuis le début.
+    pub fn rewind(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// Vérifie qu'une grille est complète, en la tirant aux dés si seule sa graine est connue.
+fn check_entry(entry: &mut CatalogueEntry, dices: &Dices) -> Result<(), CatalogueError> {
+    match (entry.grid.is_empty(), entry.seed) {
+        (true, None) => Err(CatalogueError::MissingGrid { line: entry.line }),
+        (true, Some(seed)) => {
+            entry.grid = dices.roll(seed);
+            Ok(())
+        },
+        (false, Some(seed)) if dices.roll(seed) != entry.grid =>
+            Err(CatalogueError::SeedMismatch { line: entry.line }),
+        (false, _) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::super::dict::TrieDict;
+    use rand::{SeedableRng, Isaac64Rng};
+
+    const CATALOGUE: &str = "
+# Catalogue de test
+[grille]
+nom: Trident
+difficulte: facile
+grille: LIDAREJULTNEATNG
+solutions: Trident, île
+bonus: l..M....m.....L.
+
+[grille]
+graine: 42
+
+[grille]
+grille: LIDA[QU]EJULTNEATNG
+";
+
+    fn parse(content: &str) -> Result<Catalogue, CatalogueError> {
+        Catalogue::parse(content, 4, &Dices::new(4))
+    }
+
+    #[test]
+    fn parse_ok() {
+        let catalogue = parse(CATALOGUE).unwrap();
+        assert_eq!(catalogue.entries.len(), 3);
+
+        let first = &catalogue.entries[0];
+        assert_eq!(first.name, Some("Trident".to_string()));
+        assert_eq!(first.difficulty, Some("facile".to_string()));
+        assert_eq!(first.solutions, vec!["Trident", "île"]);
+        assert_eq!(first.line, 3);
+        assert_eq!(first.to_string(), "<Trident>, difficulté facile");
+        assert_eq!(first.bonus, BonusLayout::parse("l..M....m.....L.", 4));
+        assert_eq!(catalogue.entries[1].bonus, None);
+
+        assert_eq!(catalogue.entries[1].grid, Dices::new(4).roll(42));
+        assert_eq!(catalogue.entries[2].grid[4], "QU");
+    }
+
+    #[test]
+    fn sequential_order() {
+        let mut catalogue = parse(CATALOGUE).unwrap();
+        let mut rng = Isaac64Rng::from_seed(&[42][..]);
+        let lines = (0..6).map(|_| catalogue.next_entry(&mut rng).unwrap().line).collect::<Vec<usize>>();
+        assert_eq!(lines, vec![3, 10, 13, 3, 10, 13]);
+    }
+
+    #[test]
+    fn shuffled_order_plays_every_grid() {
+        let mut catalogue = parse(CATALOGUE).unwrap().with_order(Order::Shuffled);
+        let mut rng = Isaac64Rng::from_seed(&[42][..]);
+        for _ in 0..10 {
+            let mut lines = (0..3).map(|_| catalogue.next_entry(&mut rng).unwrap().line).collect::<Vec<usize>>();
+            lines.sort();
+            assert_eq!(lines, vec![3, 10, 13]);
+        }
+    }
+
+    #[test]
+    fn rewind() {
+        let mut catalogue = parse(CATALOGUE).unwrap();
+        let mut rng = Isaac64Rng::from_seed(&[42][..]);
+        catalogue.next_entry(&mut rng);
+        catalogue.rewind();
+        assert_eq!(catalogue.next_entry(&mut rng).unwrap().line, 3);
+    }
+
+    #[test]
+    fn empty_catalogue() {
+        assert!(Catalogue::new().next_entry(&mut Isaac64Rng::from_seed(&[42][..])).is_none());
+        match parse("# rien") {
+            Err(CatalogueError::Empty) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn check_solutions() {
+        let catalogue = parse(CATALOGUE).unwrap();
+        let dict = TrieDict::from_words(vec!["trident".to_string(), "ile".to_string()]);
+        assert!(catalogue.check_solutions(4, &dict).is_ok());
+
+        let catalogue = parse("[grille]\ngrille: LIDAREJULTNEATNG\nsolutions: trident, dent").unwrap();
+        match catalogue.check_solutions(4, &dict) {
+            Err(CatalogueError::NotASolution { line: 1, ref word }) if word == "dent" => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn invalid_catalogues() {
+        let cases = vec![
+            ("grille: LIDAREJULTNEATNG", CatalogueError::MissingHeader { line: 1 }),
+            ("[grille]\nLIDAREJULTNEATNG", CatalogueError::InvalidLine { line: 2 }),
+            ("[grille]\ngrille:", CatalogueError::InvalidLine { line: 2 }),
+            ("[grille]\nauteur: moi", CatalogueError::unknown_key(2, "auteur")),
+            ("[grille]\ngraine: 1\ngraine: 2", CatalogueError::duplicate_key(3, "graine")),
+            ("[grille]\ngraine: -1", CatalogueError::invalid_seed(2, "-1")),
+            ("[grille]\ngrille: LIDAREJULTNEATNGXLIDAREJULTNEATNGX", CatalogueError::invalid_grid(2, "LIDAREJULTNEATNGXLIDAREJULTNEATNGX", 4)),
+            ("[grille]\ngrille: LIDAREJULTNEATN1", CatalogueError::invalid_grid(2, "LIDAREJULTNEATN1", 4)),
+            ("[grille]\ngraine: 1\nbonus: l..M", CatalogueError::invalid_bonus(3, "l..M", 4)),
+            ("[grille]\nnom: vide\n[grille]\ngraine: 1", CatalogueError::MissingGrid { line: 1 }),
+            ("[grille]\ngraine: 42\ngrille: LIDAREJULTNEATNG", CatalogueError::SeedMismatch { line: 1 }),
+        ];
+        for (content, expected) in cases {
+            match parse(content) {
+                Err(ref e) if e.to_string() == expected.to_string() => (),
+                other => panic!("{:?}: expected {:?}, got {:?}", content, expected, other.err())
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+/// Case de la grille, repérée par sa ligne et sa colonne (à partir de 0).
+/// Elle s'écrit sous la forme "A1": ligne A à F, colonne 1 à 6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cell {
+    line: usize,
+    column: usize,
+}
+
+impl Cell {
+    pub fn new(line: usize, column: usize) -> Cell {
+        Cell { line, column }
+    }
+
+    /// Lit la case de ligne `line` et de colonne `column` d'une grille de `size` x `size` cases.
+    pub fn of_chars(line: char, column: char, size: usize) -> Result<Cell, ()> {
+        Ok(Cell::new(line_of_char(line, size)?, column_of_char(column, size)?))
+    }
+
+    /// Renvoie toutes les cases d'une grille de `size` x `size` cases, ligne par ligne.
+    pub fn all(size: usize) -> impl Iterator<Item = Cell> {
+        (0..size).flat_map(move |line| (0..size).map(move |column| Cell::new(line, column)))
+    }
+
+    /// Renvoie la position de la case dans une grille de `size` x `size` cases.
+    pub fn index(&self, size: usize) -> usize {
+        self.line * size + self.column
+    }
+
+    /// Renvoie true si `other` est l'une des 8 cases voisines de cette case.
+    pub fn is_adjacent(&self, other: Cell) -> bool {
+        let (v_dist, h_dist) = self.distance(other);
+        v_dist.max(h_dist) == 1
+    }
+
+    fn distance(&self, other: Cell) -> (usize, usize) {
+        (self.line.abs_diff(other.line), self.column.abs_diff(other.column))
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'A' + self.line as u8) as char, self.column + 1)
+    }
+}
+
+fn column_of_char(col: char, size: usize) -> Result<usize, ()> {
+    match col.to_digit(10) {
+        Some(n) if n >= 1 && n as usize <= size => Ok(n as usize - 1),
+        _ => Err(())
+    }
+}
+
+fn line_of_char(line: char, size: usize) -> Result<usize, ()> {
+    let line = line.to_ascii_uppercase();
+    match line {
+        'A'..='Z' if ((line as u8 - b'A') as usize) < size => Ok((line as u8 - b'A') as usize),
+        _ => Err(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::GRID_SIZES;
+
+    #[test]
+    fn line_of_char_ok() {
+        let lines = vec!['A', 'B', 'C', 'D'];
+        for (idx, c) in lines.into_iter().enumerate() {
+            match line_of_char(c, 4) {
+                Ok(r) if r == idx => (),
+                _ => panic!("{} is a valid line !", c)
+            }
+        }
+        assert_eq!(line_of_char('f', 6), Ok(5));
+    }
+
+    #[test]
+    fn line_or_char_err() {
+        match line_of_char('E', 4) {
+            Err(()) => (),
+            _ => panic!("'E' is not a valid line !")
+        }
+        assert_eq!(line_of_char('F', 5), Err(()));
+        assert_eq!(line_of_char('1', 6), Err(()));
+    }
+
+    #[test]
+    fn column_of_char_ok() {
+        let cols = vec!['1', '2', '3', '4'];
+        for c in cols {
+            match column_of_char(c, 4) {
+                Ok(r) if r == c.to_digit(10).unwrap() as usize - 1 => (),
+                _ => panic!("{} is a valid column !", c),
+            }
+        }
+        assert_eq!(column_of_char('6', 6), Ok(5));
+    }
+
+    #[test]
+    fn column_of_char_err() {
+        match column_of_char('5', 4) {
+            Err(()) => (),
+            _ => panic!("'5' is not a valid column !")
+        }
+        assert_eq!(column_of_char('0', 6), Err(()));
+        assert_eq!(column_of_char('6', 5), Err(()));
+    }
+
+    #[test]
+    fn of_chars_ok() {
+        assert_eq!(Cell::of_chars('c', '2', 4), Ok(Cell::new(2, 1)));
+        assert_eq!(Cell::of_chars('F', '6', 6), Ok(Cell::new(5, 5)));
+        assert_eq!(Cell::of_chars('F', '6', 5), Err(()));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Cell::new(0, 0).to_string(), "A1");
+        assert_eq!(Cell::new(2, 1).to_string(), "C2");
+        assert_eq!(Cell::new(5, 5).to_string(), "F6");
+    }
+
+    #[test]
+    fn index() {
+        assert_eq!(Cell::new(2, 1).index(4), 9);
+        assert_eq!(Cell::new(2, 1).index(6), 13);
+        for &size in GRID_SIZES.iter() {
+            let indexes = Cell::all(size).map(|c| c.index(size)).collect::<Vec<usize>>();
+            assert_eq!(indexes, (0..size * size).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(Cell::new(1, 1).distance(Cell::new(0, 0)), (1, 1));
+        assert_eq!(Cell::new(1, 1).distance(Cell::new(0, 1)), (1, 0));
+        assert_eq!(Cell::new(1, 1).distance(Cell::new(1, 2)), (0, 1));
+        assert_eq!(Cell::new(1, 1).distance(Cell::new(1, 1)), (0, 0));
+    }
+
+    #[test]
+    fn is_adjacent_test() {
+        assert!(Cell::new(1, 1).is_adjacent(Cell::new(0, 0)));
+        assert!(Cell::new(1, 1).is_adjacent(Cell::new(1, 0)));
+        assert!(! Cell::new(1, 1).is_adjacent(Cell::new(1, 1)));
+        assert!(! Cell::new(0, 0).is_adjacent(Cell::new(0, 2)));
+        assert!(! Cell::new(0, 0).is_adjacent(Cell::new(2, 0)));
+        assert!(! Cell::new(0, 0).is_adjacent(Cell::new(1, 2)));
+    }
+
+    #[test]
+    fn is_adjacent_exhaustive() {
+        for &size in GRID_SIZES.iter() {
+            for c1 in Cell::all(size) {
+                for c2 in Cell::all(size) {
+                    let v_dist = (c1.line as i64 - c2.line as i64).abs();
+                    let h_dist = (c1.column as i64 - c2.column as i64).abs();
+                    let expected = c1 != c2 && v_dist <= 1 && h_dist <= 1;
+                    assert_eq!(c1.is_adjacent(c2), expected, "{} -> {}", c1, c2);
+                    assert_eq!(c1.is_adjacent(c2), c2.is_adjacent(c1), "{} <-> {}", c1, c2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_cell_has_at_most_eight_neighbours() {
+        for &size in GRID_SIZES.iter() {
+            for c1 in Cell::all(size) {
+                let neighbours = Cell::all(size).filter(|&c2| c1.is_adjacent(c2)).count();
+                let on_edge = |x: usize| x == 0 || x == size - 1;
+                let expected = match (on_edge(c1.line), on_edge(c1.column)) {
+                    (true, true) => 3,
+                    (true, false) | (false, true) => 5,
+                    (false, false) => 8,
+                };
+                assert_eq!(neighbours, expected, "{}", c1);
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::super::errors::ServerError;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// Objet d'une contestation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChallengeKind {
+    /// Le joueur demande l'ajout de son mot, refusé par le dictionnaire.
+    Rejected,
+    /// Le joueur demande l'annulation du mot accepté de l'adversaire `owner`.
+    Accepted { owner: String },
+}
+
+impl fmt::Display for ChallengeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChallengeKind::Rejected => write!(f, "AJOUT"),
+            ChallengeKind::Accepted { .. } => write!(f, "ANNULATION"),
+        }
+    }
+}
+
+/// Issue d'une contestation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    /// La majorité des votants a donné raison au joueur.
+    Upheld,
+    /// La contestation est rejetée.
+    Dismissed,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Verdict::Upheld => write!(f, "ACCEPTEE"),
+            Verdict::Dismissed => write!(f, "REJETEE"),
+        }
+    }
+}
+
+/// Contestation d'un mot, soumise au vote des autres joueurs pendant la pause entre deux tours.
+/// Elle est acceptée si la majorité absolue des votants vote pour: un votant qui ne s'exprime
+/// pas avant la fin de la pause compte comme un vote contre.
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub challenger: String,
+    pub word: String,
+    pub kind: ChallengeKind,
+    /// Joueurs appelés à voter: tous sauf ceux concernés par le mot.
+    voters: HashSet<String>,
+    /// Votes déjà exprimés, true pour un vote pour.
+    votes: HashMap<String, bool>,
+}
+
+impl Challenge {
+    pub fn new(challenger: &str, word: &str, kind: ChallengeKind, voters: HashSet<String>) -> Challenge {
+        Challenge {
+            challenger: challenger.to_string(),
+            word: word.to_string(),
+            kind,
+            voters,
+            votes: HashMap::new(),
+        }
+    }
+
+    /// Enregistre le vote de `voter`.
+    /// Renvoie une erreur s'il n'est pas appelé à voter ou s'il a déjà voté.
+    pub fn vote(&mut self, voter: &str, approve: bool) -> Result<(), ServerError> {
+        if ! self.voters.contains(voter) {
+            return Err(ServerError::not_a_voter(voter, &self.word))
+        }
+        if self.votes.contains_key(voter) {
+            return Err(ServerError::already_voted(voter, &self.word))
+        }
+        self.votes.insert(voter.to_string(), approve);
+        Ok(())
+    }
+
+    /// Renvoie le verdict s'il ne peut plus être changé par les votes restants.
+    pub fn verdict(&self) -> Option<Verdict> {
+        let nb_voters = self.voters.len();
+        let nb_against = self.votes.values().filter(|&&v| ! v).count();
+        if self.nb_for() * 2 > nb_voters {
+            Some(Verdict::Upheld)
+        } else if (nb_voters - nb_against) * 2 <= nb_voters {
+            Some(Verdict::Dismissed)
+        } else {
+            None
+        }
+    }
+
+    /// Renvoie le verdict à la fin de la pause, les votes manquants comptant contre.
+    pub fn final_verdict(&self) -> Verdict {
+        if self.nb_for() * 2 > self.voters.len() {
+            Verdict::Upheld
+        } else {
+            Verdict::Dismissed
+        }
+    }
+
+    fn nb_for(&self) -> usize {
+        self.votes.values().filter(|&&v| v).count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_test_challenge(nb_voters: usize) -> Challenge {
+        let voters = (0..nb_voters).map(|i| format!("user{}", i)).collect();
+        Challenge::new("challenger", "tridente", ChallengeKind::Rejected, voters)
+    }
+
+    #[test]
+    fn majority_decides_early() {
+        let mut challenge = create_test_challenge(3);
+        assert_eq!(challenge.verdict(), None);
+        challenge.vote("user0", true).unwrap();
+        assert_eq!(challenge.verdict(), None);
+        challenge.vote("user1", true).unwrap();
+        assert_eq!(challenge.verdict(), Some(Verdict::Upheld));
+
+        let mut challenge = create_test_challenge(4);
+        challenge.vote("user0", false).unwrap();
+        challenge.vote("user1", true).unwrap();
+        assert_eq!(challenge.verdict(), None);
+        challenge.vote("user2", false).unwrap();
+        assert_eq!(challenge.verdict(), Some(Verdict::Dismissed));
+    }
+
+    #[test]
+    fn missing_votes_count_against() {
+        let mut challenge = create_test_challenge(3);
+        challenge.vote("user0", true).unwrap();
+        assert_eq!(challenge.final_verdict(), Verdict::Dismissed);
+        assert_eq!(create_test_challenge(0).final_verdict(), Verdict::Dismissed);
+        assert_eq!(create_test_challenge(0).verdict(), Some(Verdict::Dismissed));
+    }
+
+    #[test]
+    fn invalid_votes() {
+        let mut challenge = create_test_challenge(2);
+        match challenge.vote("challenger", true) {
+            Err(ServerError::NotAVoter { .. }) => (),
+            _ => panic!("The challenger cannot vote !")
+        }
+        challenge.vote("user0", true).unwrap();
+        match challenge.vote("user0", false) {
+            Err(ServerError::AlreadyVoted { .. }) => (),
+            _ => panic!("user0 has already voted !")
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::super::errors::DiceError;
+
+use std::fs;
+
+use rand::{Rng, SeedableRng, Isaac64Rng};
+
+/// Jeux de dés fournis avec le serveur, par langue et taille de grille.
+/// Ils sont intégrés à l'exécutable, qui peut ainsi être lancé depuis n'importe quel répertoire.
+const BUNDLED: [(&str, usize, &str); 6] = [
+    ("fr", 4, include_str!("../../des/fr_4x4.txt")),
+    ("fr", 5, include_str!("../../des/fr_5x5.txt")),
+    ("fr", 6, include_str!("../../des/fr_6x6.txt")),
+    ("en", 4, include_str!("../../des/en_4x4.txt")),
+    ("en", 5, include_str!("../../des/en_5x5.txt")),
+    ("en", 6, include_str!("../../des/en_6x6.txt")),
+];
+
+/// Jeu de dés servant à générer les grilles aléatoires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dices {
+    dices: Vec<Vec<String>>
+}
+
+impl Dices {
+    /// Renvoie le jeu de dés français pour une grille de `size` x `size` cases.
+    /// Panique si `size` n'est pas une des tailles de `GRID_SIZES`.
+    pub fn new(size: usize) -> Dices {
+        Dices::of_language("fr", size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Renvoie le jeu de dés de la langue `language` pour une grille de `size` x `size` cases.
+    /// Les jeux qui ne sont pas fournis avec le serveur sont lus dans le répertoire `des`.
+    pub fn of_language(language: &str, size: usize) -> Result<Dices, DiceError> {
+        match BUNDLED.iter().find(|&&(l, s, _)| l == language && s == size) {
+            Some(&(_, _, content)) => Dices::parse(content, size),
+            None => Dices::from_file(&format!("des/{}_{}x{}.txt", language, size, size), size),
+        }
+    }
+
+    /// Charge le jeu de dés décrit par le fichier `file`.
+    /// Renvoie une erreur si le fichier ne contient pas exactement `size` x `size` dés valides.
+    pub fn from_file(file: &str, size: usize) -> Result<Dices, DiceError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| DiceError::unreadable_file(file, &e.to_string()))?;
+        Dices::parse(&content, size)
+    }
+
+    /// Lit un jeu de dés: un dé par ligne, faces séparées par des virgules.
+    /// Les lignes vides et celles commençant par '#' sont ignorées.
+    fn parse(content: &str, size: usize) -> Result<Dices, DiceError> {
+        let dices = content.lines()
+            .enumerate()
+            .filter(|&(_, l)| ! l.trim().is_empty() && ! l.trim_start().starts_with('#'))
+            .map(|(idx, l)| dice_of_line(l, idx + 1))
+            .collect::<Result<Vec<Vec<String>>, DiceError>>()?;
+
+        if dices.len() != size * size {
+            return Err(DiceError::wrong_dice_count(dices.len(), size))
+        }
+
+        Ok(Dices { dices })
+    }
+
+    /// Lance tous les dés et renvoie la face obtenue pour chacun d'eux.
+    /// Un même `seed` produit toujours la même grille.
+    pub fn roll(&self, seed: u64) -> Vec<String> {
+        let mut rng = Isaac64Rng::from_seed(&[seed][..]);
+
+        self.dices.iter()
+            .map(|dice| rng.choose(dice).unwrap().clone())
+            .collect()
+    }
+}
+
+fn dice_of_line(line: &str, line_number: usize) -> Result<Vec<String>, DiceError> {
+    let faces = line.split(',')
+        .map(|face| face.trim())
+        .map(|face| {
+            if face.is_empty() {
+                Err(DiceError::empty_face(line_number))
+            } else if ! face.chars().all(|c| c.is_ascii_alphabetic()) {
+                Err(DiceError::invalid_face(line_number, face))
+            } else {
+                Ok(face.to_uppercase())
+            }
+        })
+        .collect::<Result<Vec<String>, DiceError>>()?;
+
+    if faces.len() != 6 {
+        return Err(DiceError::wrong_face_count(line_number, faces.len()))
+    }
+
+    Ok(faces)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::GRID_SIZES;
+
+    #[test]
+    fn bundled_dices_are_valid() {
+        for language in &["fr", "en"] {
+            for &size in GRID_SIZES.iter() {
+                if let Err(e) = Dices::of_language(language, size) {
+                    panic!("{}", e)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bundled_dices_match_files() {
+        for &(language, size, _) in BUNDLED.iter() {
+            let file = format!("des/{}_{}x{}.txt", language, size, size);
+            assert_eq!(Dices::of_language(language, size).unwrap(), Dices::from_file(&file, size).unwrap());
+        }
+    }
+
+    #[test]
+    fn parse_ok() {
+        let content = "# commentaire\n\n".to_string() + &"A,B,C,D,E,F\n".repeat(15) + " a , b,c,d,e, qu \n";
+        let dices = Dices::parse(&content, 4).unwrap();
+        assert_eq!(dices.dices[15], vec!["A", "B", "C", "D", "E", "QU"]);
+    }
+
+    #[test]
+    fn roll_picks_one_face_per_dice() {
+        let dices = Dices::of_language("fr", 4).unwrap();
+        let grid = dices.roll(42);
+        assert_eq!(grid.len(), 16);
+        grid.iter()
+            .enumerate()
+            .for_each(|(idx, tile)| assert!(dices.dices[idx].contains(tile)))
+    }
+
+    #[test]
+    fn roll_is_reproducible() {
+        let dices = Dices::of_language("fr", 5).unwrap();
+        assert_eq!(dices.roll(42), dices.roll(42));
+        assert_ne!(dices.roll(42), dices.roll(43));
+    }
+
+    #[test]
+    fn wrong_dice_count() {
+        match Dices::from_file("des/fr_4x4.txt", 5) {
+            Err(DiceError::WrongDiceCount { found: 16, expected: 25 }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn empty_face() {
+        match Dices::parse("A,B,,D,E,F", 1) {
+            Err(DiceError::EmptyFace { line: 1 }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn invalid_face() {
+        match Dices::parse("# dé\nA,B,C,D,E,1", 1) {
+            Err(DiceError::InvalidFace { line: 2, .. }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn wrong_face_count() {
+        match Dices::parse("A,B,C,D,E", 1) {
+            Err(DiceError::WrongFaceCount { line: 1, found: 5 }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        match Dices::of_language("xx", 4) {
+            Err(DiceError::UnreadableFile { .. }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+}
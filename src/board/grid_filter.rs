@@ -0,0 +1,89 @@
+use super::Solution;
+
+/// Statistiques d'une grille, calculées à partir de ses solutions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridStats {
+    /// Graine ayant servi à générer la grille.
+    pub seed: u64,
+    /// Nombre de mots pouvant être formés sur la grille.
+    pub nb_words: usize,
+    /// Total des points de tous ces mots.
+    pub score: u32,
+    /// Nombre de lettres du plus long de ces mots.
+    pub longest: usize,
+}
+
+impl GridStats {
+    pub fn new(seed: u64, solutions: &[Solution]) -> GridStats {
+        GridStats {
+            seed,
+            nb_words: solutions.len(),
+            score: solutions.iter().map(|s| s.score).sum(),
+            longest: solutions.iter().map(|s| s.word.chars().count()).max().unwrap_or(0),
+        }
+    }
+}
+
+/// Critères de qualité que doit respecter une grille aléatoire.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridFilter {
+    /// Nombre minimum de mots pouvant être formés.
+    pub min_words: usize,
+    /// Total minimum des points de ces mots.
+    pub min_score: u32,
+    /// Nombre de lettres minimum du plus long de ces mots.
+    pub min_longest: usize,
+    /// Nombre maximum de grilles rejetées avant d'accepter la suivante quoi qu'il arrive.
+    pub max_rejections: usize,
+}
+
+impl GridFilter {
+    /// Renvoie true si une grille ayant les statistiques `stats` respecte les critères.
+    pub fn accepts(&self, stats: &GridStats) -> bool {
+        stats.nb_words >= self.min_words
+            && stats.score >= self.min_score
+            && stats.longest >= self.min_longest
+    }
+}
+
+impl Default for GridFilter {
+    /// Filtre acceptant toutes les grilles.
+    fn default() -> GridFilter {
+        GridFilter {
+            min_words: 0,
+            min_score: 0,
+            min_longest: 0,
+            max_rejections: 100,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn solution(word: &str, score: u32) -> Solution {
+        Solution { word: word.to_string(), trajectory: vec![], score }
+    }
+
+    #[test]
+    fn stats_of_solutions() {
+        let stats = GridStats::new(42, &[solution("trident", 5), solution("ile", 1)]);
+        assert_eq!(stats, GridStats { seed: 42, nb_words: 2, score: 6, longest: 7 });
+    }
+
+    #[test]
+    fn default_filter_accepts_empty_grid() {
+        assert!(GridFilter::default().accepts(&GridStats::new(0, &[])));
+    }
+
+    #[test]
+    fn filter_checks_every_bound() {
+        let stats = GridStats { seed: 0, nb_words: 20, score: 30, longest: 6 };
+        let filter = GridFilter { min_words: 20, min_score: 30, min_longest: 6, ..GridFilter::default() };
+        assert!(filter.accepts(&stats));
+        assert!(! GridFilter { min_words: 21, ..filter.clone() }.accepts(&stats));
+        assert!(! GridFilter { min_score: 31, ..filter.clone() }.accepts(&stats));
+        assert!(! GridFilter { min_longest: 7, ..filter.clone() }.accepts(&stats));
+    }
+}
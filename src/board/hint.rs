@@ -0,0 +1,151 @@
+use super::{Cell, Solution};
+
+use std::fmt;
+
+/// Indice sur un mot de la grille qu'aucun joueur n'a encore trouvé.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hint {
+    /// Première lettre du mot.
+    FirstLetter(char),
+    /// Nombre de lettres du mot.
+    Length(usize),
+    /// Case où commence le mot.
+    StartingCell(Cell),
+}
+
+impl Hint {
+    /// Renvoie le nom du type d'indice, tel qu'envoyé aux clients.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Hint::FirstLetter(_) => "LETTRE",
+            Hint::Length(_) => "LONGUEUR",
+            Hint::StartingCell(_) => "CASE",
+        }
+    }
+
+    /// Renvoie la valeur de l'indice, telle qu'envoyée aux clients.
+    pub fn value(&self) -> String {
+        match *self {
+            Hint::FirstLetter(letter) => letter.to_string(),
+            Hint::Length(length) => length.to_string(),
+            Hint::StartingCell(cell) => cell.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Hint::FirstLetter(letter) => write!(f, "première lettre {}", letter),
+            Hint::Length(length) => write!(f, "longueur {}", length),
+            Hint::StartingCell(cell) => write!(f, "case de départ {}", cell),
+        }
+    }
+}
+
+/// Indices donnés à un joueur lors du tour courant.
+/// Les indices portent sur un même mot tant qu'il n'a pas été trouvé, en devenant
+/// de plus en plus précis: première lettre, puis longueur, puis case de départ.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HintState {
+    /// Mots ayant fait l'objet d'indices, le dernier étant le mot en cours.
+    words: Vec<String>,
+    /// Nombre d'indices déjà donnés sur le mot en cours.
+    level: usize,
+}
+
+impl HintState {
+    /// Renvoie l'indice suivant parmi les `solutions` de la grille, en ignorant
+    /// les mots pour lesquels `is_found` renvoie true.
+    /// Renvoie `None` s'il ne reste aucun mot sur lequel donner un indice.
+    pub fn next_hint<F>(&mut self, solutions: &[Solution], is_found: F) -> Option<Hint>
+        where F: Fn(&str) -> bool
+    {
+        let current = self.words.last()
+            .filter(|w| self.level < 3 && ! is_found(w))
+            .and_then(|w| solutions.iter().find(|s| &s.word == w));
+        let solution = match current {
+            Some(solution) => solution,
+            None => {
+                let solution = solutions.iter()
+                    .find(|s| ! is_found(&s.word) && ! self.words.contains(&s.word))?;
+                self.words.push(solution.word.clone());
+                self.level = 0;
+                solution
+            }
+        };
+
+        let hint = match self.level {
+            0 => Hint::FirstLetter(solution.word.chars().next()?),
+            1 => Hint::Length(solution.word.chars().count()),
+            _ => Hint::StartingCell(*solution.trajectory.first()?),
+        };
+        self.level += 1;
+        Some(hint)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn solution(word: &str, start: Cell) -> Solution {
+        Solution { word: word.to_string(), trajectory: vec![start], score: 1 }
+    }
+
+    fn create_test_solutions() -> Vec<Solution> {
+        vec![solution("trident", Cell::new(2, 1)), solution("ile", Cell::new(0, 1))]
+    }
+
+    #[test]
+    fn hints_escalate() {
+        let mut state = HintState::default();
+        let solutions = create_test_solutions();
+        let hints = (0..5)
+            .map(|_| state.next_hint(&solutions, |_| false))
+            .collect::<Vec<Option<Hint>>>();
+        assert_eq!(hints, vec![
+            Some(Hint::FirstLetter('t')),
+            Some(Hint::Length(7)),
+            Some(Hint::StartingCell(Cell::new(2, 1))),
+            Some(Hint::FirstLetter('i')),
+            Some(Hint::Length(3)),
+        ]);
+    }
+
+    #[test]
+    fn hints_skip_found_words() {
+        let mut state = HintState::default();
+        let solutions = create_test_solutions();
+        assert_eq!(state.next_hint(&solutions, |_| false), Some(Hint::FirstLetter('t')));
+        assert_eq!(state.next_hint(&solutions, |w| w == "trident"), Some(Hint::FirstLetter('i')));
+        assert_eq!(state.next_hint(&solutions, |_| true), None);
+    }
+
+    #[test]
+    fn no_hint_when_every_word_was_hinted() {
+        let mut state = HintState::default();
+        let solutions = create_test_solutions();
+        for _ in 0..6 {
+            assert!(state.next_hint(&solutions, |_| false).is_some());
+        }
+        assert_eq!(state.next_hint(&solutions, |_| false), None);
+    }
+
+    #[test]
+    fn kind_and_value() {
+        let hints = [Hint::FirstLetter('t'), Hint::Length(7), Hint::StartingCell(Cell::new(2, 1))];
+        let fields = hints.iter()
+            .map(|h| (h.kind(), h.value()))
+            .collect::<Vec<(&str, String)>>();
+        assert_eq!(fields, vec![("LETTRE", "t".to_string()), ("LONGUEUR", "7".to_string()),
+                                ("CASE", "C2".to_string())]);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Hint::FirstLetter('t').to_string(), "première lettre t");
+        assert_eq!(Hint::Length(7).to_string(), "longueur 7");
+        assert_eq!(Hint::StartingCell(Cell::new(2, 1)).to_string(), "case de départ C2");
+    }
+}
@@ -1,6 +1,25 @@
+#[allow(clippy::module_inception)]
 pub mod board;
+pub mod bonus;
+pub mod catalogue;
+pub mod challenge;
+pub mod cell;
+pub mod dices;
+pub mod grid_filter;
+pub mod hint;
+pub mod scoring;
+pub mod solver;
 
 pub use self::board::Board;
+pub use self::bonus::BonusLayout;
+pub use self::catalogue::{Catalogue, Order};
+pub use self::challenge::{Challenge, ChallengeKind, Verdict};
+pub use self::cell::Cell;
+pub use self::dices::Dices;
+pub use self::grid_filter::{GridFilter, GridStats};
+pub use self::hint::{Hint, HintState};
+pub use self::scoring::{Classic, ScoreTable, Scrabble, ScoringRule};
+pub use self::solver::Solution;
 
 use super::{
     errors::{ServerError},
@@ -10,63 +29,61 @@ use std::{
     collections::{HashMap, HashSet}
 };
 
-use regex::Regex;
-use rand::{self, Rng};
+/// Tailles de grille supportées: Boggle (4x4), Big Boggle (5x5) et Super Big Boggle (6x6).
+pub const GRID_SIZES: [usize; 3] = [4, 5, 6];
+
+/// Lit une grille de `size` x `size` cases.
+/// Chaque case est une lettre, ou plusieurs lettres entre crochets (ex: "[QU]").
+fn grid_of_string(s: &str, size: usize) -> Option<Vec<String>> {
+    let mut grid = Vec::with_capacity(size * size);
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        let tile = match c {
+            '[' => multi_letter_tile(&mut chars)?,
+            c => c.to_string(),
+        };
+        if tile.is_empty() || ! tile.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None
+        }
+        grid.push(tile.to_uppercase());
+    }
 
-lazy_static! {
-    static ref GRID_REGEX: Regex = Regex::new("[a-zA-Z]{16}").unwrap();
+    if grid.len() == size * size {
+        Some(grid)
+    } else {
+        None
+    }
 }
 
-const DICES: [[char; 6]; 16] = [
-    ['E', 'T', 'U', 'K', 'N', 'O'],
-    ['E', 'V', 'G', 'T', 'I', 'N'],
-    ['D', 'E', 'C', 'A', 'M', 'P'],
-    ['I', 'E', 'L', 'R', 'U', 'W'],
-    ['E', 'H', 'I', 'F', 'S', 'E'],
-    ['R', 'E', 'C', 'A', 'L', 'S'],
-    ['E', 'N', 'T', 'D', 'O', 'S'],
-    ['O', 'F', 'X', 'R', 'I', 'A'],
-    ['N', 'A', 'V', 'E', 'D', 'Z'],
-    ['E', 'I', 'O', 'A', 'T', 'A'],
-    ['G', 'L', 'E', 'N', 'Y', 'U'],
-    ['B', 'M', 'A', 'Q', 'J', 'O'],
-    ['T', 'L', 'I', 'B', 'R', 'A'],
-    ['S', 'P', 'U', 'L', 'T', 'E'],
-    ['A', 'I', 'M', 'S', 'O', 'R'],
-    ['E', 'N', 'H', 'R', 'I', 'S'],
-];
-
-fn generate_random_grid() -> [char; 16] {
-    let mut rng = rand::thread_rng();
-    let mut result = ['A'; 16];
-
-    DICES.iter()
-        .map(|dice| rng.choose(dice).unwrap())
-        .enumerate()
-        .for_each(|(idx, &letter)| result[idx] = letter);
-
-    result
+/// Lit les lettres d'une case jusqu'au crochet fermant.
+fn multi_letter_tile<I: Iterator<Item = char>>(chars: &mut I) -> Option<String> {
+    let mut tile = String::new();
+    loop {
+        match chars.next()? {
+            ']' => return Some(tile),
+            c => tile.push(c),
+        }
+    }
 }
 
-fn grid_of_string(s: &str) -> Option<[char; 16]> {
-    if GRID_REGEX.is_match(s) {
-        let mut result = ['A'; 16];
-        s.chars().enumerate().for_each(|(idx, c)| result[idx] = c);
-        Some(result)
-    } else {
-        None
-    }
+/// Renvoie la représentation textuelle de la grille `grid`,
+/// les cases de plusieurs lettres étant placées entre crochets.
+fn string_of_grid(grid: &[String]) -> String {
+    grid.iter()
+        .map(|tile| if tile.len() == 1 { tile.clone() } else { format!("[{}]", tile) })
+        .collect()
 }
 
-fn trajectory_of_string(t: &str) -> Result<Vec<(char, usize)>, ServerError> {
-    if t.len() % 2 == 1 || t.len() < 6 {
+fn trajectory_of_string(t: &str, size: usize) -> Result<Vec<Cell>, ServerError> {
+    let chars: Vec<char> = t.chars().collect();
+    if chars.len() % 2 == 1 || chars.len() < 4 {
         return Err(ServerError::bad_trajectory(t))
     }
 
-    let chars: Vec<char> = t.chars().collect();
     let trajectory = chars.chunks(2)
-        .map(|chunk| coordinates_of_chars(chunk[0], chunk[1]))
-        .collect::<Result<Vec<(char, usize)>, _>>()
+        .map(|chunk| Cell::of_chars(chunk[0], chunk[1], size))
+        .collect::<Result<Vec<Cell>, _>>()
         .map_err(|_| ServerError::bad_trajectory(t))?;
 
     if is_valid_trajectory(&trajectory) {
@@ -76,42 +93,11 @@ fn trajectory_of_string(t: &str) -> Result<Vec<(char, usize)>, ServerError> {
     }
 }
 
-fn coordinates_of_chars(line: char, column: char) -> Result<(char, usize), ()> {
-    let c = column_of_char(column)?;
-    let l = line_of_char(line)?;
-    Ok((l, c))
-}
-
-fn column_of_char(col: char) -> Result<usize, ()> {
-    match col.to_digit(10) {
-        Some(n @ 1...4) => Ok(n as usize),
-        _ => Err(())
-    }
-}
-
-fn line_of_char(line: char) -> Result<char, ()> {
-    match line {
-        'a' | 'A' => Ok('A'),
-        'b' | 'B' => Ok('B'),
-        'c' | 'C' => Ok('C'),
-        'd' | 'D' => Ok('D'),
-        _ => Err(())
-    }
-}
-
-pub fn is_valid_trajectory(t: &[(char, usize)]) -> bool {
-    if contains_doubles(t) {
-        return false
-    }
-    for i in 0..t.len() - 1 {
-        if ! is_valid_distance(t[i], t[i + 1]) {
-            return false
-        }
-    }
-    true
+pub fn is_valid_trajectory(t: &[Cell]) -> bool {
+    ! contains_doubles(t) && t.windows(2).all(|w| w[0].is_adjacent(w[1]))
 }
 
-fn contains_doubles(trajectory: &[(char, usize)]) -> bool {
+fn contains_doubles(trajectory: &[Cell]) -> bool {
     let mut seen = HashSet::new();
     for c in trajectory {
         if seen.contains(c) {
@@ -122,35 +108,15 @@ fn contains_doubles(trajectory: &[(char, usize)]) -> bool {
     false
 }
 
-fn is_valid_distance(square1: (char, usize), square2: (char, usize)) -> bool {
-    let (v_dist, h_dist) = distance(square1, square2);
-    let s = v_dist + h_dist;
-    s > 0 && s <= 2
-}
-
-fn distance(square1: (char, usize), square2: (char, usize)) -> (i8, i8) {
-    ((square1.0 as i8 - square2.0 as i8).abs(),
-     (square1.1 as i8 - square2.1 as i8).abs())
-}
-
-fn word_score(word: &str) -> u32 {
-    match word.len() {
-        0...2 => 0,
-        3...4 => 1,
-        5 => 2,
-        6 => 3,
-        7 => 5,
-        _ => 11
-    }
-}
-
-fn index_of_letter(letter: char) -> usize {
-    match letter {
-        'A' => 0,
-        'B' => 1,
-        'C' => 2,
-        'D' => 3,
-        _ => panic!("Invalid character index !")
+/// Sépare une ligne de fichier de configuration de la forme `clé: valeur`.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let pos = line.find(':')?;
+    let key = line[..pos].trim();
+    let value = line[pos + 1..].trim();
+    if key.is_empty() || value.is_empty() {
+        None
+    } else {
+        Some((key, value))
     }
 }
 
@@ -159,78 +125,71 @@ mod test {
     use super::*;
 
     #[test]
-    fn random_grid() {
-       generate_random_grid().iter()
-           .enumerate()
-           .for_each(|(idx, c)| assert!(DICES[idx].contains(c)))
+    fn grid_of_string_ok() {
+        assert_eq!(grid_of_string("LIDAREJULTNEATNG", 4).map(|g| g.len()), Some(16));
+        assert_eq!(grid_of_string(&"A".repeat(25), 5).map(|g| g.len()), Some(25));
     }
 
     #[test]
-    fn line_of_char_ok() {
-        let lines = vec!['A', 'B', 'C', 'D'];
-        for c in lines {
-            match line_of_char(c) {
-                Ok(r) if r == c => (),
-                _ => panic!("{} is a valid line !", c)
-            }
-        }
+    fn grid_of_string_multi_letter_tiles() {
+        let grid = grid_of_string("LIDA[QU]EJULTNEATN[ch]", 4).unwrap();
+        assert_eq!(grid[4], "QU");
+        assert_eq!(grid[15], "CH");
+        assert_eq!(string_of_grid(&grid), "LIDA[QU]EJULTNEATN[CH]");
     }
 
     #[test]
-    fn line_or_char_err() {
-        match line_of_char('E') {
-            Err(()) => (),
-            _ => panic!("'E' is not a valid line !")
-        }
+    fn grid_of_string_err() {
+        assert_eq!(grid_of_string("LIDAREJULTNEATNG", 5), None);
+        assert_eq!(grid_of_string("LIDAREJULTNEATNGA", 4), None);
+        assert_eq!(grid_of_string("LIDAREJULTNEATN1", 4), None);
+        assert_eq!(grid_of_string("LIDAREJULTNEATN[]", 4), None);
+        assert_eq!(grid_of_string("LIDAREJULTNEATN[QU", 4), None);
+        assert_eq!(grid_of_string("LIDAREJULTNEATN[Q1]", 4), None);
     }
 
     #[test]
-    fn column_of_char_ok() {
-        let cols = vec!['1', '2', '3', '4'];
-        for c in cols {
-            match column_of_char(c) {
-                Ok(r) if r == c.to_digit(10).unwrap() as usize => (),
-                _ => panic!("{} is a valid column !", c),
-            }
-        }
+    fn string_of_single_letter_grid() {
+        let grid = grid_of_string("LIDAREJULTNEATNG", 4).unwrap();
+        assert_eq!(string_of_grid(&grid), "LIDAREJULTNEATNG");
     }
 
     #[test]
-    fn column_of_char_err() {
-        match column_of_char('5') {
-            Err(()) => (),
-            _ => panic!("'5' is not a valid column !")
+    fn trajectory_of_string_ok() {
+        let expected = vec![Cell::new(2, 1), Cell::new(1, 0), Cell::new(0, 1), Cell::new(0, 2),
+                            Cell::new(1, 1), Cell::new(2, 2), Cell::new(3, 1)];
+        let trajectory = "C2B1A2A3B2C3D2";
+        match trajectory_of_string(trajectory, 4) {
+            Ok(ref t) if t == &expected => (),
+            _ => panic!("Trajectory {} is invalid !", trajectory)
         }
     }
 
     #[test]
-    fn distance_test() {
-        assert_eq!(distance(('B', 2), ('A', 1)), (1, 1));
-        assert_eq!(distance(('B', 2), ('A', 2)), (1, 0));
-        assert_eq!(distance(('B', 2), ('B', 3)), (0, 1));
-        assert_eq!(distance(('B', 2), ('B', 2)), (0, 0));
+    fn trajectory_of_string_big_grid() {
+        assert_eq!(trajectory_of_string("E5F6F5", 6).unwrap(),
+                   vec![Cell::new(4, 4), Cell::new(5, 5), Cell::new(5, 4)]);
+        assert!(trajectory_of_string("E5F6F5", 5).is_err());
     }
 
     #[test]
-    fn is_valid_distance_test() {
-        assert!(is_valid_distance(('B', 2), ('A', 1)));
-        assert!(is_valid_distance(('B', 2), ('B', 1)));
-        assert!(! is_valid_distance(('B', 2), ('B', 2)));
+    fn trajectory_of_string_multibyte() {
+        assert!(trajectory_of_string("é1A2A", 4).is_err());
+        assert!(trajectory_of_string("A1B2é", 4).is_err());
+        assert!(trajectory_of_string("A1B2Cé", 4).is_err());
     }
 
     #[test]
-    fn trajectory_of_string_ok() {
-        let expected = vec![('C', 2), ('B', 1), ('A', 2), ('A', 3), ('B', 2), ('C', 3), ('D', 2)];
-        let trajectory = "C2B1A2A3B2C3D2";
-        match trajectory_of_string(trajectory) {
-            Ok(ref t) if t == &expected => (),
-            _ => panic!("Trajectory {} is invalid !", trajectory)
-        }
+    fn trajectory_of_string_rejects_jumps() {
+        assert!(trajectory_of_string("A1A3A4", 4).is_err());
+        assert!(trajectory_of_string("A1C1D1", 4).is_err());
+        assert!(trajectory_of_string("A1B3C4", 4).is_err());
+        assert!(trajectory_of_string("A1B2C3", 4).is_ok());
     }
 
     #[test]
     fn contains_duplicates() {
-        let trajectory = vec![('A', 2), ('B', 1), ('A', 2)];
+        let trajectory = vec![Cell::new(0, 1), Cell::new(1, 0), Cell::new(0, 1)];
         assert!(contains_doubles(&trajectory))
     }
 }
\ No newline at end of file
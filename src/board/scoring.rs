@@ -0,0 +1,205 @@
+use super::split_line;
+use super::super::errors::ScoringError;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+};
+
+/// Règle de calcul des points rapportés par un mot.
+pub trait ScoringRule: Send + Sync {
+    /// Renvoie le nombre de points rapportés par le mot `word`, en minuscules.
+    fn word_score(&self, word: &str) -> u32;
+
+    /// Renvoie la valeur de la lettre `letter`, en minuscule, gagnée à nouveau
+    /// lorsqu'elle est posée sur une case lettre compte double ou triple.
+    fn letter_score(&self, letter: char) -> u32;
+}
+
+/// Barème classique du Boggle, fonction de la longueur du mot.
+/// Une case de plusieurs lettres compte pour autant de lettres qu'elle en contient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Classic;
+
+impl ScoringRule for Classic {
+    fn word_score(&self, word: &str) -> u32 {
+        match word.chars().count() {
+            0..=2 => 0,
+            3..=4 => 1,
+            5 => 2,
+            6 => 3,
+            7 => 5,
+            _ => 11
+        }
+    }
+
+    /// Chaque lettre vaut un point.
+    fn letter_score(&self, _letter: char) -> u32 {
+        1
+    }
+}
+
+/// Barème du Scrabble français: chaque lettre rapporte sa valeur.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scrabble;
+
+impl ScoringRule for Scrabble {
+    fn word_score(&self, word: &str) -> u32 {
+        word.chars().map(|c| self.letter_score(c)).sum()
+    }
+
+    fn letter_score(&self, letter: char) -> u32 {
+        match letter {
+            'a' | 'e' | 'i' | 'l' | 'n' | 'o' | 'r' | 's' | 't' | 'u' => 1,
+            'd' | 'g' | 'm' => 2,
+            'b' | 'c' | 'p' => 3,
+            'f' | 'h' | 'v' => 4,
+            'j' | 'q' => 8,
+            'k' | 'w' | 'x' | 'y' | 'z' => 10,
+            _ => 0
+        }
+    }
+}
+
+/// Barème configurable, chargé depuis un fichier de lignes `clé: points`:
+///
+/// ```text
+/// # Barème maison: 1 point par mot de 3 ou 4 lettres, 2 points au delà,
+/// # et 5 points de plus par lettre k ou w.
+/// 3: 1
+/// 5: 2
+/// k: 5
+/// w: 5
+/// ```
+///
+/// Une clé numérique donne les points d'un mot d'au moins autant de lettres, jusqu'à la
+/// longueur suivante du barème. Une clé d'une lettre donne les points ajoutés pour
+/// chaque occurrence de cette lettre.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreTable {
+    lengths: BTreeMap<usize, u32>,
+    letters: HashMap<char, u32>,
+}
+
+impl ScoreTable {
+    /// Charge le barème décrit par le fichier `file`.
+    pub fn from_file(file: &str) -> Result<ScoreTable, ScoringError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| ScoringError::unreadable_file(file, &e.to_string()))?;
+        ScoreTable::parse(&content)
+    }
+
+    /// Lit un barème depuis son contenu `content`.
+    fn parse(content: &str) -> Result<ScoreTable, ScoringError> {
+        let mut table = ScoreTable { lengths: BTreeMap::new(), letters: HashMap::new() };
+
+        for (idx, line) in content.lines().enumerate() {
+            let line_number = idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let (key, value) = split_line(line).ok_or(ScoringError::InvalidLine { line: line_number })?;
+            let points = value.parse::<u32>()
+                .map_err(|_| ScoringError::invalid_points(line_number, value))?;
+            let duplicate = if let Ok(length) = key.parse::<usize>() {
+                table.lengths.insert(length, points).is_some()
+            } else {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphabetic() =>
+                        table.letters.insert(c.to_ascii_lowercase(), points).is_some(),
+                    _ => return Err(ScoringError::invalid_key(line_number, key)),
+                }
+            };
+            if duplicate {
+                return Err(ScoringError::duplicate_key(line_number, key))
+            }
+        }
+
+        if table.lengths.is_empty() && table.letters.is_empty() {
+            return Err(ScoringError::Empty)
+        }
+        Ok(table)
+    }
+}
+
+impl ScoringRule for ScoreTable {
+    fn word_score(&self, word: &str) -> u32 {
+        let length_score = self.lengths.range(..=word.chars().count())
+            .next_back()
+            .map_or(0, |(_, &points)| points);
+        let letters_score = word.chars()
+            .map(|c| self.letter_score(c))
+            .sum::<u32>();
+        length_score + letters_score
+    }
+
+    /// Les lettres absentes du barème ne valent aucun point.
+    fn letter_score(&self, letter: char) -> u32 {
+        self.letters.get(&letter).cloned().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classic() {
+        let scores = ["il", "ile", "lire", "tenue", "trient", "trident", "tridents", "tridentes"].iter()
+            .map(|w| Classic.word_score(w))
+            .collect::<Vec<u32>>();
+        assert_eq!(scores, vec![0, 1, 1, 2, 3, 5, 11, 11]);
+    }
+
+    #[test]
+    fn scrabble() {
+        assert_eq!(Scrabble.word_score("ile"), 3);
+        assert_eq!(Scrabble.word_score("quiz"), 20);
+        assert_eq!(Scrabble.word_score("trident"), 8);
+    }
+
+    #[test]
+    fn table_reproduces_classic() {
+        let table = ScoreTable::parse("# Barème classique\n3: 1\n5: 2\n6: 3\n7: 5\n8: 11\n").unwrap();
+        for word in &["il", "ile", "lire", "tenue", "trient", "trident", "tridents", "tridentes"] {
+            assert_eq!(table.word_score(word), Classic.word_score(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn table_with_letters() {
+        let table = ScoreTable::parse("3: 1\nK: 5\nw: 5").unwrap();
+        assert_eq!(table.word_score("kiwi"), 11);
+        assert_eq!(table.word_score("ki"), 5);
+        assert_eq!(table.word_score("ile"), 1);
+    }
+
+    #[test]
+    fn invalid_tables() {
+        let cases = vec![
+            ("# vide", ScoringError::Empty),
+            ("3 1", ScoringError::InvalidLine { line: 1 }),
+            ("3: -1", ScoringError::invalid_points(1, "-1")),
+            ("3: 1\nqu: 8", ScoringError::invalid_key(2, "qu")),
+            ("3: 1\n3: 2", ScoringError::duplicate_key(2, "3")),
+            ("k: 1\nK: 2", ScoringError::duplicate_key(2, "K")),
+        ];
+        for (content, expected) in cases {
+            match ScoreTable::parse(content) {
+                Err(ref e) if e.to_string() == expected.to_string() => (),
+                other => panic!("{:?}: expected {:?}, got {:?}", content, expected, other.err())
+            }
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        match ScoreTable::from_file("bareme_inexistant.txt") {
+            Err(ScoringError::UnreadableFile { .. }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+use super::super::dict::Dict;
+
+/// Mot du dictionnaire pouvant être formé sur une grille.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// Mot trouvé, en minuscules.
+    pub word: String,
+    /// Trajectoire permettant de former le mot sur la grille.
+    pub trajectory: Vec<Cell>,
+    /// Nombre de points rapportés par le mot.
+    pub score: u32,
+}
+
+/// Renvoie tous les mots de `dict` pouvant être formés sur la grille `grid`, avec leurs
+/// points selon `scoring`, triés par score décroissant puis par ordre alphabétique.
+pub fn solve(grid: &[String], size: usize, dict: &dyn Dict, scoring: &dyn ScoringRule) -> Vec<Solution> {
+    let mut found = HashMap::new();
+    let mut trajectory = Vec::new();
+    let mut word = String::new();
+
+    for cell in Cell::all(size) {
+        trajectory.push(cell);
+        explore(grid, size, dict, &mut trajectory, &mut word, &mut found);
+        trajectory.pop();
+    }
+
+    let mut solutions = found.into_iter()
+        .map(|(word, trajectory)| Solution { score: scoring.word_score(&word), word, trajectory })
+        .collect::<Vec<Solution>>();
+    solutions.sort_by(|s1, s2| s2.score.cmp(&s1.score).then_with(|| s1.word.cmp(&s2.word)));
+    solutions
+}
+
+/// Parcourt en profondeur les trajectoires prolongeant `trajectory`, en respectant
+/// les règles d'adjacence et de non réutilisation des cases de `is_valid_trajectory`.
+/// Les branches ne formant le préfixe d'aucun mot de `dict` sont abandonnées.
+fn explore(grid: &[String], size: usize, dict: &dyn Dict, trajectory: &mut Vec<Cell>,
+           word: &mut String, found: &mut HashMap<String, Vec<Cell>>)
+{
+    let last = trajectory[trajectory.len() - 1];
+    let len = word.len();
+    word.push_str(&grid[last.index(size)].to_lowercase());
+
+    if dict.has_prefix(word) {
+        if word.len() >= 3 && ! found.contains_key(word.as_str()) && dict.contains(word) {
+            found.insert(word.clone(), trajectory.clone());
+        }
+        for cell in Cell::all(size) {
+            if last.is_adjacent(cell) && ! trajectory.contains(&cell) {
+                trajectory.push(cell);
+                explore(grid, size, dict, trajectory, word, found);
+                trajectory.pop();
+            }
+        }
+    }
+
+    word.truncate(len);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::super::dict::{LocalDict, TrieDict};
+
+    fn create_test_grid() -> Vec<String> {
+        grid_of_string("LIDAREJULTNEATNG", 4).unwrap()
+    }
+
+    fn create_test_dict(words: &[&str]) -> TrieDict {
+        TrieDict::from_words(words.iter().map(|w| w.to_string()))
+    }
+
+    #[test]
+    fn solve_finds_every_word() {
+        let dict = LocalDict::from_dictionary("dico_test.txt");
+        let words = solve(&create_test_grid(), 4, &dict, &Classic).into_iter()
+            .map(|s| s.word)
+            .collect::<Vec<String>>();
+        assert_eq!(words, vec!["trident", "ile"]);
+    }
+
+    #[test]
+    fn solutions_are_valid_trajectories() {
+        let dict = create_test_dict(&["trident", "ile", "tenue", "lire", "dent"]);
+        let solutions = solve(&create_test_grid(), 4, &dict, &Classic);
+        assert!(! solutions.is_empty());
+        for solution in solutions {
+            assert!(is_valid_trajectory(&solution.trajectory));
+            assert_eq!(solution.score, Classic.word_score(&solution.word));
+        }
+    }
+
+    #[test]
+    fn solve_returns_trajectory() {
+        let dict = create_test_dict(&["trident"]);
+        let expected = trajectory_of_string("C2B1A2A3B2C3D2", 4).unwrap();
+        assert_eq!(solve(&create_test_grid(), 4, &dict, &Classic)[0].trajectory, expected);
+    }
+
+    #[test]
+    fn solve_does_not_reuse_squares() {
+        let dict = create_test_dict(&["ded", "dad", "ile"]);
+        let words = solve(&create_test_grid(), 4, &dict, &Classic).into_iter()
+            .map(|s| s.word)
+            .collect::<Vec<String>>();
+        assert_eq!(words, vec!["ile"]);
+    }
+
+    #[test]
+    fn solve_big_grid() {
+        let grid = grid_of_string("LIDAXREJUXLTNEXATNGXXXXXY", 5).unwrap();
+        let dict = create_test_dict(&["trident", "xxy", "axy"]);
+        let solutions = solve(&grid, 5, &dict, &Classic);
+        let words = solutions.iter().map(|s| s.word.as_str()).collect::<Vec<&str>>();
+        assert_eq!(words, vec!["trident", "xxy"]);
+        assert_eq!(solutions[1].trajectory.last(), Some(&Cell::new(4, 4)));
+    }
+
+    #[test]
+    fn solve_multi_letter_tiles() {
+        let grid = grid_of_string("LIDA[QU]EJULTNEATNG", 4).unwrap();
+        let dict = create_test_dict(&["que", "quel", "qe"]);
+        let solutions = solve(&grid, 4, &dict, &Classic);
+        let words = solutions.iter().map(|s| s.word.as_str()).collect::<Vec<&str>>();
+        assert_eq!(words, vec!["que", "quel"]);
+        assert_eq!(solutions[0].trajectory, vec![Cell::new(1, 0), Cell::new(1, 1)]);
+    }
+
+    #[test]
+    fn solve_uses_strict_adjacency() {
+        let dict = create_test_dict(&["lla", "gna", "ile"]);
+        let words = solve(&create_test_grid(), 4, &dict, &Classic).into_iter()
+            .map(|s| s.word)
+            .collect::<Vec<String>>();
+        assert_eq!(words, vec!["ile"]);
+    }
+
+    #[test]
+    fn solve_uses_scoring_rule() {
+        let dict = create_test_dict(&["trident", "ile"]);
+        let solutions = solve(&create_test_grid(), 4, &dict, &Scrabble);
+        assert_eq!(solutions[0].score, Scrabble.word_score("trident"));
+        assert_eq!(solutions[1].score, Scrabble.word_score("ile"));
+    }
+
+    #[test]
+    fn solve_ignores_short_words() {
+        let dict = create_test_dict(&["il", "ri"]);
+        assert!(solve(&create_test_grid(), 4, &dict, &Classic).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use super::protocol::{Encoding, MessageSink, ServerMessage};
+
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::{mpsc::{self, error::TrySendError}, watch},
+};
+
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+/// Nombre maximum de messages en attente d'envoi vers un client.
+pub const QUEUE_LEN: usize = 256;
+
+/// Élément de la file d'envoi d'un client.
+enum Outgoing {
+    Message(Vec<u8>),
+    /// Fermeture de la connexion, une fois les messages précédents envoyés.
+    Close,
+}
+
+/// Flux d'écriture vers un client, partageable entre les tâches du serveur.
+///
+/// Les messages sont placés dans une file bornée, vidée vers la socket par une tâche dédiée:
+/// une écriture ne bloque jamais. Un client qui ne lit pas ses messages assez vite pour que
+/// sa file se vide est déconnecté, plutôt que de ralentir les autres joueurs.
+#[derive(Clone)]
+pub struct ClientWriter {
+    queue: mpsc::Sender<Outgoing>,
+    closed: Arc<watch::Sender<bool>>,
+    encoding: Encoding,
+}
+
+impl ClientWriter {
+    /// Crée le flux d'écriture vers `stream` et démarre la tâche qui y envoie les messages.
+    pub fn new<W: AsyncWrite + Unpin + Send + 'static>(stream: W) -> ClientWriter {
+        ClientWriter::with_queue_len(stream, QUEUE_LEN)
+    }
+
+    fn with_queue_len<W: AsyncWrite + Unpin + Send + 'static>(stream: W, queue_len: usize) -> ClientWriter {
+        let (queue, messages) = mpsc::channel(queue_len);
+        let closed = Arc::new(watch::channel(false).0);
+        tokio::spawn(send_messages(stream, messages, closed.clone()));
+        ClientWriter { queue, closed, encoding: Encoding::Text }
+    }
+
+    /// Renvoie le format des messages échangés avec le client.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Définit le format des messages échangés avec le client.
+    /// Seules les copies du flux faites par la suite l'utilisent.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Ferme la connexion après l'envoi des messages en attente, ou immédiatement si la file
+    /// d'envoi est pleine.
+    pub fn shutdown(self) {
+        if self.queue.try_send(Outgoing::Close).is_err() {
+            self.closed.send_replace(true);
+        }
+    }
+
+    /// Renvoie un récepteur signalant la fermeture de la connexion, à la demande du serveur,
+    /// parce que le client est trop lent ou parce que la socket n'accepte plus d'écriture.
+    pub fn closing(&self) -> watch::Receiver<bool> {
+        self.closed.subscribe()
+    }
+}
+
+impl Write for ClientWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        match self.queue.try_send(Outgoing::Message(buf.to_vec())) {
+            Ok(()) => Ok(buf.len()),
+            Err(TrySendError::Full(_)) => {
+                self.closed.send_replace(true);
+                Err(io::Error::new(io::ErrorKind::WouldBlock, "file d'envoi pleine, client déconnecté"))
+            },
+            Err(TrySendError::Closed(_)) => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl MessageSink for ClientWriter {
+    fn send(&mut self, message: &ServerMessage) -> Result<(), io::Error> {
+        self.write_all(self.encoding.encode(message).as_bytes())
+    }
+}
+
+/// Envoie sur `stream` les messages de la file `messages`, jusqu'à la fermeture de la connexion.
+/// Quand tous les flux d'écriture ont disparu sans fermer la connexion, les messages en attente
+/// sont envoyés avant de la fermer.
+async fn send_messages<W: AsyncWrite + Unpin>(mut stream: W, mut messages: mpsc::Receiver<Outgoing>,
+                                              closed: Arc<watch::Sender<bool>>)
+{
+    let mut closing = closed.subscribe();
+    loop {
+        let message = tokio::select! {
+            message = messages.recv() => message,
+            Ok(_) = closing.wait_for(|&closed| closed) => None,
+        };
+        let message = match message {
+            Some(Outgoing::Message(message)) => message,
+            Some(Outgoing::Close) | None => break,
+        };
+        tokio::select! {
+            result = stream.write_all(&message) => if result.is_err() { break },
+            Ok(_) = closing.wait_for(|&closed| closed) => break,
+        }
+    }
+    let _ = stream.shutdown().await;
+    closed.send_replace(true);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{duplex, AsyncReadExt};
+
+    #[tokio::test]
+    async fn messages_are_sent_in_order() {
+        let (stream, mut client) = duplex(1024);
+        let mut writer = ClientWriter::new(stream);
+        writer.write_all(b"SESSION/\n").unwrap();
+        writer.clone().write_all(b"TOUR/ABCD/\n").unwrap();
+        drop(writer);
+        let mut received = String::new();
+        client.read_to_string(&mut received).await.unwrap();
+        assert_eq!(received, "SESSION/\nTOUR/ABCD/\n");
+    }
+
+    #[tokio::test]
+    async fn shutdown_closes_connection() {
+        let (stream, mut client) = duplex(1024);
+        let mut writer = ClientWriter::new(stream);
+        let mut closing = writer.closing();
+        let _other = writer.clone();
+        writer.write_all(b"ERREUR/PSEUDO_PRIS/Pseudo pris./\n").unwrap();
+        writer.shutdown();
+        let mut received = String::new();
+        client.read_to_string(&mut received).await.unwrap();
+        assert_eq!(received, "ERREUR/PSEUDO_PRIS/Pseudo pris./\n");
+        closing.wait_for(|&closed| closed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn slow_client_is_disconnected() {
+        // Le client ne lit jamais: la tâche d'envoi reste bloquée sur le premier message.
+        let (stream, _client) = duplex(1);
+        let mut writer = ClientWriter::with_queue_len(stream, 2);
+        let mut closing = writer.closing();
+        let errors = (0..10).filter(|_| writer.write_all(b"TOUR/ABCD/\n").is_err()).count();
+        assert!(errors > 0);
+        closing.wait_for(|&closed| closed).await.unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+use super::{Dict, Normalizer, TrieDict};
+use super::super::errors::DictError;
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    convert::TryFrom,
+    fs::{self, File},
+    io,
+};
+
+use memmap2::Mmap;
+#[cfg(test)]
+use memmap2::MmapMut;
+
+/// En-tête d'un dictionnaire compilé, suivi du numéro de version du format.
+const MAGIC: &[u8] = b"BOGDICT";
+const VERSION: u8 = 2;
+/// Position de la normalisation des mots dans l'en-tête.
+const NORMALIZER_POS: usize = MAGIC.len() + 1;
+/// Position de la position du noeud racine dans l'en-tête.
+const ROOT_POS: usize = NORMALIZER_POS + 1;
+/// Taille de l'en-tête: signature, version, normalisation et position du noeud racine.
+const HEADER_LEN: usize = ROOT_POS + 4;
+/// Taille d'un fils: sa lettre et sa position.
+const CHILD_LEN: usize = 5;
+
+/// Dictionnaire précompilé, interrogé directement dans sa représentation binaire.
+///
+/// Le fichier contient un trie dont les sous-arbres identiques ne sont stockés qu'une
+/// fois. Chaque noeud est écrit sous la forme `[fin de mot: u8][nb fils: u8]` suivie,
+/// pour chaque fils trié par lettre, de `[lettre: u8][position: u32]`. Les positions sont
+/// absolues, en little endian: le fichier est projeté en mémoire et interrogé tel quel,
+/// sans être lu ni désérialisé au démarrage.
+/// La normalisation utilisée lors de la compilation est enregistrée dans l'en-tête pour être
+/// appliquée à l'identique lors des recherches.
+pub struct CompiledDict {
+    bytes: Mmap,
+    root: usize,
+    normalizer: Normalizer,
+}
+
+impl CompiledDict {
+    /// Charge le dictionnaire compilé `file` en le projetant en mémoire:
+    /// seules les pages parcourues par les recherches sont lues.
+    /// Le fichier ne doit pas être modifié tant que le dictionnaire est utilisé.
+    pub fn from_file(file: &str) -> Result<CompiledDict, DictError> {
+        let unreadable = |e: io::Error| DictError::unreadable_file(file, &e.to_string());
+        let f = File::open(file).map_err(unreadable)?;
+        // Sûr tant que le fichier n'est pas tronqué ou réécrit pendant l'exécution,
+        // ce que la documentation de `from_file` exclut.
+        let map = unsafe { Mmap::map(&f) }.map_err(unreadable)?;
+        CompiledDict::from_map(map)
+    }
+
+    #[cfg(test)]
+    fn from_bytes(bytes: Vec<u8>) -> Result<CompiledDict, DictError> {
+        let mut map = MmapMut::map_anon(bytes.len()).unwrap();
+        map.copy_from_slice(&bytes);
+        CompiledDict::from_map(map.make_read_only().unwrap())
+    }
+
+    fn from_map(bytes: Mmap) -> Result<CompiledDict, DictError> {
+        if ! CompiledDict::is_compiled(&bytes) || bytes.len() < HEADER_LEN {
+            return Err(DictError::InvalidFormat)
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(DictError::UnsupportedVersion { version: bytes[MAGIC.len()] })
+        }
+        let root = read_u32(&bytes, ROOT_POS)
+            .filter(|&root| root + 2 <= bytes.len())
+            .ok_or(DictError::InvalidFormat)?;
+        let normalizer = Normalizer::of_flags(bytes[NORMALIZER_POS]);
+        Ok(CompiledDict { bytes, root, normalizer })
+    }
+
+    /// Renvoie true si `bytes` commence par l'en-tête d'un dictionnaire compilé.
+    pub fn is_compiled(bytes: &[u8]) -> bool {
+        bytes.starts_with(MAGIC)
+    }
+
+    /// Compile le dictionnaire `dict`.
+    /// Ses mots ne doivent contenir que des caractères ASCII.
+    pub fn compile(dict: &TrieDict) -> Result<Vec<u8>, DictError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(dict.normalizer().to_flags());
+        bytes.extend_from_slice(&[0; 4]);
+        let root = write_node(dict, 0, &mut bytes, &mut HashMap::new())?;
+        bytes[ROOT_POS..HEADER_LEN].copy_from_slice(&root.to_le_bytes());
+        Ok(bytes)
+    }
+
+    /// Compile la liste de mots `source`, un mot par ligne normalisé par `normalizer`,
+    /// dans le fichier `destination`. Renvoie la taille du dictionnaire compilé, en octets.
+    pub fn compile_file(source: &str, destination: &str, normalizer: Normalizer) -> Result<usize, DictError> {
+        let content = fs::read_to_string(source)
+            .map_err(|e| DictError::unreadable_file(source, &e.to_string()))?;
+        let bytes = CompiledDict::compile(&TrieDict::from_text(&content, normalizer))?;
+        // Le dictionnaire est écrit à côté puis renommé: un serveur utilisant déjà
+        // `destination` garde sa projection de l'ancien fichier, intacte.
+        let tmp = format!("{}.tmp", destination);
+        fs::write(&tmp, &bytes)
+            .and_then(|_| fs::rename(&tmp, destination))
+            .map_err(|e| DictError::unwritable_file(destination, &e.to_string()))?;
+        Ok(bytes.len())
+    }
+
+    /// Renvoie le fils `letter` du noeud situé à la position `node`, s'il existe.
+    fn child(&self, node: usize, letter: u8) -> Option<usize> {
+        let nb_children = *self.bytes.get(node + 1)? as usize;
+        let children = self.bytes.get(node + 2..node + 2 + nb_children * CHILD_LEN)?;
+        let (mut low, mut high) = (0, nb_children);
+        while low < high {
+            let mid = (low + high) / 2;
+            match children[mid * CHILD_LEN].cmp(&letter) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return read_u32(children, mid * CHILD_LEN + 1),
+            }
+        }
+        None
+    }
+
+    /// Renvoie la position du noeud atteint en suivant les lettres de `prefix`, s'il existe.
+    fn walk(&self, prefix: &str) -> Option<usize> {
+        self.normalizer.normalize(prefix)?.bytes()
+            .try_fold(self.root, |node, letter| self.child(node, letter))
+    }
+}
+
+impl Dict for CompiledDict {
+    fn contains(&self, word: &str) -> bool {
+        self.walk(word).and_then(|node| self.bytes.get(node)).is_some_and(|&terminal| terminal == 1)
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        self.walk(prefix).is_some()
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+}
+
+/// Écrit le noeud `node` de `dict` et ses descendants à la fin de `bytes`, et renvoie sa position.
+/// Un noeud identique à un noeud déjà écrit n'est pas écrit à nouveau.
+fn write_node(dict: &TrieDict, node: usize, bytes: &mut Vec<u8>, written: &mut HashMap<Vec<u8>, u32>)
+    -> Result<u32, DictError>
+{
+    let children = dict.children(node);
+    let mut encoded = vec![dict.is_terminal(node) as u8, children.len() as u8];
+    for &(letter, child) in children {
+        if ! letter.is_ascii() {
+            return Err(DictError::NonAsciiLetter { letter })
+        }
+        let position = write_node(dict, child, bytes, written)?;
+        encoded.push(letter as u8);
+        encoded.extend_from_slice(&position.to_le_bytes());
+    }
+
+    if let Some(&position) = written.get(&encoded) {
+        return Ok(position)
+    }
+    let position = u32::try_from(bytes.len()).map_err(|_| DictError::TooLarge)?;
+    bytes.extend_from_slice(&encoded);
+    written.insert(encoded, position);
+    Ok(position)
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Option<usize> {
+    let slice = bytes.get(pos..pos + 4)?;
+    Some(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]) as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    fn words() -> Vec<String> {
+        ["trident", "tri", "ile", "pri", "prise", "trise"].iter().map(|w| w.to_string()).collect()
+    }
+
+    fn create_test_dict() -> CompiledDict {
+        let bytes = CompiledDict::compile(&TrieDict::from_words(words())).unwrap();
+        CompiledDict::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn contains_ok() {
+        let dict = create_test_dict();
+        for word in words() {
+            assert!(dict.contains(&word), "{}", word);
+        }
+        assert!(dict.contains("TRI"));
+        assert!(! dict.contains("trid"));
+        assert!(! dict.contains("tridents"));
+        assert!(dict.contains("Île"));
+        assert!(! dict.contains("c3po"));
+    }
+
+    #[test]
+    fn has_prefix_ok() {
+        let dict = create_test_dict();
+        assert!(dict.has_prefix(""));
+        assert!(dict.has_prefix("trid"));
+        assert!(dict.has_prefix("Il"));
+        assert!(dict.has_prefix("pris"));
+        assert!(! dict.has_prefix("tridents"));
+        assert!(! dict.has_prefix("x"));
+    }
+
+    #[test]
+    fn identical_subtries_are_shared() {
+        let shared = CompiledDict::compile(&TrieDict::from_words(words())).unwrap();
+        let words = vec!["tri".to_string(), "pri".to_string()];
+        let single = CompiledDict::compile(&TrieDict::from_words(vec!["tri".to_string()])).unwrap();
+        let double = CompiledDict::compile(&TrieDict::from_words(words)).unwrap();
+        // "pri" réutilise le noeud "ri" de "tri": seul le fils 'p' de la racine est ajouté.
+        assert_eq!(double.len(), single.len() + CHILD_LEN);
+        // Les fins de mot "se" de "trise" et "prise", et "e" de "ile", sont partagées.
+        let trie_size = 2 * 18 + CHILD_LEN * 17;
+        assert!(shared.len() - HEADER_LEN < trie_size);
+    }
+
+    #[test]
+    fn compile_file_ok() {
+        let file = env::temp_dir().join("boggle_compiled_dict_test.bin");
+        CompiledDict::compile_file("dico_test.txt", file.to_str().unwrap(), Normalizer::new()).unwrap();
+        let dict = CompiledDict::from_file(file.to_str().unwrap()).unwrap();
+        fs::remove_file(&file).unwrap();
+        assert!(dict.contains("trident"));
+        assert!(dict.contains("ile"));
+    }
+
+    #[test]
+    fn invalid_files() {
+        let valid = CompiledDict::compile(&TrieDict::from_words(words())).unwrap();
+        let mut wrong_version = valid.clone();
+        wrong_version[MAGIC.len()] = 1;
+        let mut wrong_root = valid.clone();
+        wrong_root[ROOT_POS..HEADER_LEN].copy_from_slice(&[0xff; 4]);
+
+        assert!(CompiledDict::from_bytes(b"trident\nile\n".to_vec()).is_err());
+        assert!(CompiledDict::from_bytes(valid[..HEADER_LEN - 1].to_vec()).is_err());
+        assert!(CompiledDict::from_bytes(wrong_root).is_err());
+        match CompiledDict::from_bytes(wrong_version) {
+            Err(DictError::UnsupportedVersion { version: 1 }) => (),
+            _ => panic!("This call should return an error !")
+        }
+        match CompiledDict::from_file("non_existing_dict.bin") {
+            Err(DictError::UnreadableFile { .. }) => (),
+            _ => panic!("This call should return an error !")
+        }
+    }
+
+    #[test]
+    fn corrupted_file_does_not_panic() {
+        let mut bytes = CompiledDict::compile(&TrieDict::from_words(words())).unwrap();
+        let root = read_u32(&bytes, ROOT_POS).unwrap();
+        // Le premier fils de la racine, 'i', pointe en dehors du fichier.
+        bytes[root + 3..root + 3 + 4].copy_from_slice(&[0xff; 4]);
+        let dict = CompiledDict::from_bytes(bytes).unwrap();
+        assert!(! dict.contains("ile"));
+        assert!(! dict.has_prefix("il"));
+        assert!(dict.contains("trident"));
+    }
+
+    #[test]
+    fn normalizer_is_kept() {
+        let normalizer = Normalizer::new().with_punctuation_removal(false);
+        let bytes = CompiledDict::compile(&TrieDict::from_text("arc-en-ciel\nlyon", normalizer)).unwrap();
+        let dict = CompiledDict::from_bytes(bytes).unwrap();
+        assert_eq!(dict.normalizer(), &normalizer);
+        assert!(dict.contains("Lyon"));
+        assert!(! dict.contains("arcenciel"));
+        assert!(! dict.contains("arc-en-ciel"));
+    }
+}
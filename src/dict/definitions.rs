@@ -0,0 +1,101 @@
+use super::{Normalizer, word_of_line};
+use super::super::errors::DictError;
+use super::super::protocol::ServerMessage;
+
+use std::{
+    collections::HashMap,
+    fs,
+};
+
+/// Nombre maximum de définitions envoyées à la fin d'un tour.
+const MAX_DEFINITIONS: usize = 10;
+
+/// Définitions courtes des mots, consultables par les joueurs.
+///
+/// Chaque ligne du fichier contient un mot, suivi d'une tabulation et de sa définition.
+/// Les lignes sans définition sont ignorées: un dictionnaire accompagné de définitions peut
+/// ainsi servir à la fois de liste de mots et de fichier de définitions.
+pub struct Definitions {
+    definitions: HashMap<String, String>,
+    normalizer: Normalizer,
+}
+
+impl Definitions {
+    /// Charge les définitions du fichier `file`, dont les mots sont normalisés par `normalizer`.
+    pub fn from_file(file: &str, normalizer: Normalizer) -> Result<Definitions, DictError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| DictError::unreadable_file(file, &e.to_string()))?;
+        Ok(Definitions::from_text(&content, normalizer))
+    }
+
+    /// Lit les définitions de `content`, à raison d'une par ligne.
+    pub fn from_text(content: &str, normalizer: Normalizer) -> Definitions {
+        let mut definitions = HashMap::new();
+        for line in content.lines() {
+            let definition = line.split_once('\t').map_or("", |(_, d)| d.trim());
+            let word = normalizer.normalize(word_of_line(line)).filter(|w| ! w.is_empty());
+            if let (Some(word), false) = (word, definition.is_empty()) {
+                definitions.entry(word.into_owned()).or_insert_with(|| definition.to_string());
+            }
+        }
+        Definitions { definitions, normalizer }
+    }
+
+    /// Renvoie la définition du mot `word`, si elle existe.
+    pub fn get(&self, word: &str) -> Option<&str> {
+        let word = self.normalizer.normalize(word)?;
+        self.definitions.get(word.as_ref()).map(|d| d.as_str())
+    }
+
+    /// Renvoie le message donnant la définition des premiers mots de `words` qui en ont une,
+    /// ou `None` si aucun n'en a.
+    pub fn section_message(&self, words: &[String]) -> Option<ServerMessage> {
+        let definitions = words.iter()
+            .filter_map(|w| self.get(w).map(|d| (w.clone(), d.to_string())))
+            .take(MAX_DEFINITIONS)
+            .collect::<Vec<(String, String)>>();
+        if definitions.is_empty() {
+            None
+        } else {
+            Some(ServerMessage::Definitions(definitions))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_test_definitions() -> Definitions {
+        Definitions::from_text("trident\tFourche à trois dents.\nile\nÎle\tTerre entourée d'eau.\n\
+                                tenue\t  \ndent\tOrgane de la mastication.\ndent\tAutre.", Normalizer::new())
+    }
+
+    #[test]
+    fn get() {
+        let definitions = create_test_definitions();
+        assert_eq!(definitions.get("TRIDENT"), Some("Fourche à trois dents."));
+        assert_eq!(definitions.get("ile"), Some("Terre entourée d'eau."));
+        assert_eq!(definitions.get("dent"), Some("Organe de la mastication."));
+        assert_eq!(definitions.get("tenue"), None);
+        assert_eq!(definitions.get("c3po"), None);
+    }
+
+    #[test]
+    fn section_str() {
+        let definitions = create_test_definitions();
+        let words = ["tenue", "trident", "ile"].iter().map(|w| w.to_string()).collect::<Vec<String>>();
+        assert_eq!(definitions.section_message(&words).map(|m| m.encode()),
+                   Some("DEFINITIONS/trident*Fourche à trois dents.*ile*Terre entourée d'eau./\n".to_string()));
+        assert_eq!(definitions.section_message(&words[..1]), None);
+    }
+
+    #[test]
+    fn section_is_limited() {
+        let content = (0..20).map(|i| format!("mot{}\tdéfinition", "a".repeat(i))).collect::<Vec<String>>();
+        let definitions = Definitions::from_text(&content.join("\n"), Normalizer::new());
+        let words = (0..20).map(|i| format!("mot{}", "a".repeat(i))).collect::<Vec<String>>();
+        let section = definitions.section_message(&words).unwrap().encode();
+        assert_eq!(section.split('*').count(), 2 * MAX_DEFINITIONS);
+    }
+}
@@ -0,0 +1,376 @@
+use super::{Dict, Normalizer, Rejection};
+use super::super::errors::DictError;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Délai maximal d'une requête au service, par défaut.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Nombre de réponses conservées en cache, par défaut.
+const DEFAULT_CACHE_SIZE: usize = 100_000;
+/// Délai pendant lequel le service n'est plus interrogé après un échec, par défaut.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Dictionnaire interrogeant un service HTTP partagé entre plusieurs serveurs.
+///
+/// Le service répond à `GET <url>/mots/<mot>` par un statut 200 si le mot existe, et 404 sinon.
+/// Les mots sont normalisés avant d'être envoyés. Les réponses sont conservées dans un cache LRU, et le
+/// dictionnaire de secours éventuel est consulté lorsque le service ne répond pas à temps.
+/// Après un échec, le service n'est plus interrogé avant un certain délai.
+///
+/// Seuls les mots soumis par les joueurs sont envoyés au service: les grilles sont résolues
+/// avec le dictionnaire de secours, qui sert d'instantané local du service.
+pub struct HttpDict {
+    host: String,
+    port: u16,
+    path: String,
+    timeout: Duration,
+    retry_delay: Duration,
+    /// Date avant laquelle le service n'est plus interrogé, après un échec.
+    retry_at: Mutex<Option<Instant>>,
+    normalizer: Normalizer,
+    words: Mutex<LruCache>,
+    fallback: Option<Box<dyn Dict>>,
+}
+
+impl HttpDict {
+    /// Dictionnaire interrogeant le service situé à l'adresse `url`, de la forme
+    /// `http://hôte[:port][/chemin]`.
+    pub fn new(url: &str) -> Result<HttpDict, DictError> {
+        let invalid = || DictError::invalid_url(url);
+        let address = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match address.find('/') {
+            Some(pos) => address.split_at(pos),
+            None => (address, ""),
+        };
+        let (host, port) = match authority.rfind(':') {
+            Some(pos) => (&authority[..pos], authority[pos + 1..].parse::<u16>().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid())
+        }
+
+        Ok(HttpDict {
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            retry_at: Mutex::new(None),
+            normalizer: Normalizer::new(),
+            words: Mutex::new(LruCache::new(DEFAULT_CACHE_SIZE)),
+            fallback: None,
+        })
+    }
+
+    /// Définit le délai au-delà duquel une requête au service échoue.
+    pub fn with_timeout(mut self, timeout: Duration) -> HttpDict {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Définit le nombre de mots dont la réponse est conservée en cache.
+    pub fn with_cache_size(mut self, size: usize) -> HttpDict {
+        self.words = Mutex::new(LruCache::new(size));
+        self
+    }
+
+    /// Définit la normalisation appliquée aux mots avant de les envoyer au service.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> HttpDict {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Définit le dictionnaire consulté lorsque le service ne répond pas.
+    pub fn with_fallback(mut self, fallback: Box<dyn Dict>) -> HttpDict {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Renvoie la réponse du service pour `key` dans la ressource `resource`,
+    /// en la cherchant d'abord dans le cache `cache`.
+    fn lookup(&self, cache: &Mutex<LruCache>, resource: &str, key: &str) -> Result<bool, DictError> {
+        let key = match self.normalizer.normalize(key) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        if let Some(found) = cache.lock().unwrap().get(&key) {
+            return Ok(found)
+        }
+        let found = self.request(&format!("{}/{}/{}", self.path, resource, key))?;
+        cache.lock().unwrap().insert(&key, found);
+        Ok(found)
+    }
+
+    /// Envoie une requête GET sur `path` et renvoie true pour un statut 200, false pour un statut 404.
+    /// Échoue immédiatement si une requête précédente a échoué il y a moins de `retry_delay`.
+    fn request(&self, path: &str) -> Result<bool, DictError> {
+        let url = format!("http://{}:{}{}", self.host, self.port, path);
+        if self.retry_at.lock().unwrap().is_some_and(|at| Instant::now() < at) {
+            return Err(DictError::unreachable(&url, "service indisponible lors de la dernière requête"))
+        }
+        let result = self.send_request(&url, path);
+        if result.is_err() {
+            *self.retry_at.lock().unwrap() = Some(Instant::now() + self.retry_delay);
+        }
+        result
+    }
+
+    fn send_request(&self, url: &str, path: &str) -> Result<bool, DictError> {
+        let unreachable = |e: &dyn ToString| DictError::unreachable(url, &e.to_string());
+
+        let address = (self.host.as_str(), self.port).to_socket_addrs()
+            .map_err(|e| unreachable(&e))?
+            .next()
+            .ok_or_else(|| unreachable(&"adresse introuvable"))?;
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout).map_err(|e| unreachable(&e))?;
+        stream.set_read_timeout(Some(self.timeout)).map_err(|e| unreachable(&e))?;
+        stream.set_write_timeout(Some(self.timeout)).map_err(|e| unreachable(&e))?;
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, self.host)
+            .map_err(|e| unreachable(&e))?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line).map_err(|e| unreachable(&e))?;
+        match status_line.split_whitespace().nth(1) {
+            Some("200") => Ok(true),
+            Some("404") => Ok(false),
+            _ => Err(DictError::unexpected_response(url, status_line.trim())),
+        }
+    }
+}
+
+impl Dict for HttpDict {
+    /// Consulte uniquement le dictionnaire de secours, sans interroger le service.
+    fn contains(&self, word: &str) -> bool {
+        self.fallback.as_ref().is_some_and(|d| d.contains(word))
+    }
+
+    /// Consulte uniquement le dictionnaire de secours, sans interroger le service.
+    fn has_prefix(&self, prefix: &str) -> bool {
+        self.fallback.as_ref().is_some_and(|d| d.has_prefix(prefix))
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    /// Consulte le dictionnaire de secours, s'il existe, lorsque le service ne répond pas.
+    fn rejection(&self, word: &str) -> Option<Rejection> {
+        let normalized = match self.normalizer.normalize(word) {
+            Some(word) => word,
+            None => return Some(Rejection::InvalidCharacters),
+        };
+        match (self.try_contains(&normalized), &self.fallback) {
+            (Ok(true), _) => None,
+            (Ok(false), _) => Some(Rejection::Unknown),
+            (Err(_), Some(fallback)) => fallback.rejection(word),
+            (Err(e), None) => Some(Rejection::Unavailable(e.to_string())),
+        }
+    }
+
+    /// Interroge le service, sans consulter le dictionnaire de secours.
+    fn try_contains(&self, word: &str) -> Result<bool, DictError> {
+        self.lookup(&self.words, "mots", word)
+    }
+}
+
+/// Cache conservant les `capacity` réponses les plus récemment consultées.
+struct LruCache {
+    capacity: usize,
+    /// Réponse associée à chaque clé, avec la date de sa dernière consultation.
+    entries: HashMap<String, (bool, u64)>,
+    /// Clés triées par date de dernière consultation.
+    ages: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> LruCache {
+        LruCache { capacity, entries: HashMap::new(), ages: BTreeMap::new(), clock: 0 }
+    }
+
+    fn get(&mut self, key: &str) -> Option<bool> {
+        let (value, age) = self.entries.get_mut(key)?;
+        self.clock += 1;
+        let key = self.ages.remove(age)?;
+        *age = self.clock;
+        self.ages.insert(self.clock, key);
+        Some(*value)
+    }
+
+    fn insert(&mut self, key: &str, value: bool) {
+        if self.capacity == 0 {
+            return
+        }
+        self.clock += 1;
+        if let Some((_, age)) = self.entries.insert(key.to_string(), (value, self.clock)) {
+            self.ages.remove(&age);
+        }
+        self.ages.insert(self.clock, key.to_string());
+        if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.ages.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::TrieDict;
+    use std::{
+        net::TcpListener,
+        sync::{Arc, atomic::{AtomicUsize, Ordering}},
+        thread,
+    };
+
+    /// Démarre un service de dictionnaire répondant pour les mots `words`.
+    /// Les requêtes sur un mot commençant par "lent" ne reçoivent jamais de réponse.
+    /// Renvoie l'adresse du service et le nombre de requêtes reçues.
+    fn start_stub_service(words: &'static [&'static str]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/dico", listener.local_addr().unwrap());
+        let nb_requests = Arc::new(AtomicUsize::new(0));
+        let counter = nb_requests.clone();
+        thread::spawn(move || {
+            let mut unanswered = vec![];
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let lines = BufReader::new(stream.try_clone().unwrap()).lines()
+                    .map(|l| l.unwrap())
+                    .take_while(|l| ! l.is_empty())
+                    .collect::<Vec<String>>();
+                let path = lines[0].split_whitespace().nth(1).unwrap_or("").to_string();
+                let found = match path.split('/').collect::<Vec<&str>>()[..] {
+                    ["", "dico", _, key] if key.starts_with("lent") => { unanswered.push(stream); continue },
+                    ["", "dico", "mots", word] => words.contains(&word),
+                    _ => { write!(stream, "HTTP/1.1 500 Internal Server Error\r\n\r\n").unwrap(); continue }
+                };
+                let status = if found { "200 OK" } else { "404 Not Found" };
+                write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            }
+        });
+        (url, nb_requests)
+    }
+
+    fn create_test_dict(words: &'static [&'static str]) -> (HttpDict, Arc<AtomicUsize>) {
+        let (url, nb_requests) = start_stub_service(words);
+        let dict = HttpDict::new(&url).unwrap().with_timeout(Duration::from_millis(100));
+        (dict, nb_requests)
+    }
+
+    #[test]
+    fn lookups() {
+        let (dict, _) = create_test_dict(&["trident", "ile"]);
+        assert!(dict.try_contains("trident").unwrap());
+        assert!(dict.try_contains("Île").unwrap());
+        assert!(! dict.try_contains("tri").unwrap());
+        assert!(! dict.try_contains("c3po").unwrap());
+        assert_eq!(dict.rejection("tridents"), Some(Rejection::Unknown));
+        assert_eq!(dict.rejection("trident"), None);
+    }
+
+    #[test]
+    fn solver_queries_use_fallback_only() {
+        let (dict, nb_requests) = create_test_dict(&["trident"]);
+        assert!(! dict.contains("trident"));
+        assert!(! dict.has_prefix("tri"));
+        let dict = dict.with_fallback(Box::new(TrieDict::from_words(vec!["ile"])));
+        assert!(dict.contains("ile"));
+        assert!(! dict.contains("trident"));
+        assert!(dict.has_prefix("il"));
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn answers_are_cached() {
+        let (dict, nb_requests) = create_test_dict(&["trident", "ile"]);
+        let dict = dict.with_cache_size(2);
+        for _ in 0..3 {
+            assert!(dict.try_contains("trident").unwrap());
+            assert!(! dict.try_contains("tri").unwrap());
+        }
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 2);
+        // "ile" remplace "tri", la réponse consultée le moins récemment.
+        assert!(dict.try_contains("trident").unwrap());
+        assert!(dict.try_contains("ile").unwrap());
+        assert!(dict.try_contains("trident").unwrap());
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 3);
+        assert!(! dict.try_contains("tri").unwrap());
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn timeout_uses_fallback() {
+        let (dict, _) = create_test_dict(&["lenteur"]);
+        match dict.try_contains("lenteur") {
+            Err(DictError::Unreachable { .. }) => (),
+            _ => panic!("The service never answers !")
+        }
+        match dict.rejection("lenteur") {
+            Some(Rejection::Unavailable(_)) => (),
+            r => panic!("Unexpected rejection: {:?}", r)
+        }
+
+        let dict = dict.with_fallback(Box::new(TrieDict::from_words(vec!["lenteur"])));
+        assert_eq!(dict.rejection("lenteur"), None);
+    }
+
+    #[test]
+    fn failures_are_not_retried_immediately() {
+        let (dict, nb_requests) = create_test_dict(&["lenteur", "trident"]);
+        assert!(dict.try_contains("lenteur").is_err());
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 1);
+        let start = Instant::now();
+        assert!(dict.try_contains("lenteur").is_err());
+        assert!(dict.try_contains("trident").is_err());
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 1);
+
+        *dict.retry_at.lock().unwrap() = Some(Instant::now());
+        assert!(dict.try_contains("trident").unwrap());
+        assert_eq!(nb_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unreachable_service() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let dict = HttpDict::new(&url).unwrap();
+        assert!(dict.try_contains("trident").is_err());
+        let dict = dict.with_fallback(Box::new(TrieDict::from_words(vec!["trident"])));
+        assert_eq!(dict.rejection("trident"), None);
+    }
+
+    #[test]
+    fn unexpected_status() {
+        let (url, _) = start_stub_service(&[]);
+        let dict = HttpDict::new(&url.replace("/dico", "/autre")).unwrap();
+        match dict.try_contains("trident") {
+            Err(DictError::UnexpectedResponse { .. }) => (),
+            _ => panic!("The service answers with a 500 status !")
+        }
+    }
+
+    #[test]
+    fn invalid_urls() {
+        for url in &["https://dico.fr", "dico.fr", "http://:80", "http://dico.fr:port/"] {
+            match HttpDict::new(url) {
+                Err(DictError::InvalidUrl { .. }) => (),
+                _ => panic!("{} is invalid !", url)
+            }
+        }
+        let dict = HttpDict::new("http://dico.fr:8080/api/v1/").unwrap();
+        assert_eq!((dict.host.as_str(), dict.port, dict.path.as_str()), ("dico.fr", 8080, "/api/v1"));
+        assert_eq!(HttpDict::new("http://dico.fr").unwrap().port, 80);
+    }
+}
@@ -1,15 +1,15 @@
-use super::Dict;
+use super::{Dict, Normalizer, word_of_line};
 
 use std::{
-    collections::HashSet,
+    collections::BTreeSet,
     fs::File,
     io::{BufReader, prelude::*},
+    ops::Bound,
 };
 
-use unidecode::unidecode;
-
 pub struct LocalDict {
-    words: HashSet<String>
+    words: BTreeSet<String>,
+    normalizer: Normalizer,
 }
 
 impl LocalDict {
@@ -18,22 +18,39 @@ impl LocalDict {
     }
 
     pub fn from_dictionary(file: &str) -> LocalDict {
-        let words = LocalDict::load_dictionary(file);
-        LocalDict { words }
+        let normalizer = Normalizer::new();
+        let words = LocalDict::load_dictionary(file, normalizer);
+        LocalDict { words, normalizer }
     }
 
-    fn load_dictionary(file: &str) -> HashSet<String> {
-        let f = File::open(file).expect(&format!("Cannot open file: {}", file));
+    fn load_dictionary(file: &str, normalizer: Normalizer) -> BTreeSet<String> {
+        let f = File::open(file).unwrap_or_else(|_| panic!("Cannot open file: {}", file));
         let reader = BufReader::new(f);
         reader.lines()
-            .map(|l| unidecode(&l.expect(&format!("Error while reading dictionary: {}", file))))
+            .map(|l| l.unwrap_or_else(|_| panic!("Error while reading dictionary: {}", file)))
+            .filter_map(|l| normalizer.normalize(word_of_line(&l)).map(|w| w.into_owned()))
+            .filter(|w| ! w.is_empty())
             .collect()
     }
 }
 
 impl Dict for LocalDict {
     fn contains(&self, word: &str) -> bool {
-        self.words.contains(&word.to_lowercase())
+        self.normalizer.normalize(word).is_some_and(|w| self.words.contains(w.as_ref()))
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        let prefix = match self.normalizer.normalize(prefix) {
+            Some(prefix) => prefix,
+            None => return false,
+        };
+        self.words.range::<str, _>((Bound::Included(prefix.as_ref()), Bound::Unbounded))
+            .next()
+            .is_some_and(|w| w.starts_with(prefix.as_ref()))
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
     }
 }
 
@@ -42,6 +59,15 @@ impl Dict for LocalDict {
 mod test {
     use super::*;
 
+    #[test]
+    fn has_prefix_ok() {
+        let dict = LocalDict::from_dictionary("dico_test.txt");
+        assert!(dict.has_prefix("tri"));
+        assert!(dict.has_prefix("ILE"));
+        assert!(! dict.has_prefix("iles"));
+        assert!(! dict.has_prefix("a"));
+    }
+
     #[test]
     #[should_panic]
     fn new_panics_on_invalid_dict_file() {
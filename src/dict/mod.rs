@@ -1,9 +1,174 @@
+mod compiled_dict;
+mod definitions;
+mod http_dict;
+#[allow(dead_code)]
 mod local_dict;
+mod normalizer;
+mod overlay_dict;
+mod trie_dict;
 
-use std::marker::Send;
+use super::errors::DictError;
 
+use std::{
+    fmt,
+    fs::File,
+    io::Read,
+    marker::{Send, Sync},
+};
+
+pub use self::compiled_dict::CompiledDict;
+pub use self::definitions::Definitions;
+pub use self::http_dict::HttpDict;
+#[allow(unused_imports)]
 pub use self::local_dict::LocalDict;
+pub use self::normalizer::Normalizer;
+pub use self::overlay_dict::OverlayDict;
+pub use self::trie_dict::TrieDict;
 
-pub trait Dict: Send {
+pub trait Dict: Send + Sync {
+    /// Renvoie true si le mot `word` est dans le dictionnaire.
+    /// Le solveur l'appelle pour chaque mot candidat: un dictionnaire distant doit y répondre
+    /// sans interroger son service, les mots soumis par les joueurs étant vérifiés par `rejection`.
     fn contains(&self, word: &str) -> bool;
-}
\ No newline at end of file
+
+    /// Renvoie true si au moins un mot du dictionnaire commence par `prefix`.
+    /// Comme `contains`, appelée à chaque case explorée par le solveur.
+    fn has_prefix(&self, prefix: &str) -> bool;
+
+    /// Renvoie la normalisation appliquée aux mots du dictionnaire et aux recherches.
+    fn normalizer(&self) -> &Normalizer;
+
+    /// Comme `contains`, mais renvoie une erreur si le dictionnaire n'a pas pu être consulté.
+    fn try_contains(&self, word: &str) -> Result<bool, DictError> {
+        Ok(self.contains(word))
+    }
+
+    /// Renvoie la raison pour laquelle le mot `word` est refusé, ou `None` s'il est accepté.
+    fn rejection(&self, word: &str) -> Option<Rejection> {
+        let word = match self.normalizer().normalize(word) {
+            Some(word) => word,
+            None => return Some(Rejection::InvalidCharacters),
+        };
+        match self.try_contains(&word) {
+            Ok(true) => None,
+            Ok(false) => Some(Rejection::Unknown),
+            Err(e) => Some(Rejection::Unavailable(e.to_string())),
+        }
+    }
+
+    /// Recharge les fichiers dont dépend le dictionnaire, s'il en a.
+    fn reload(&mut self) -> Result<(), DictError> {
+        Ok(())
+    }
+
+    /// Fait accepter le mot `word`, à la demande de `author`.
+    /// La modification est conservée au prochain chargement du dictionnaire.
+    fn add_word(&mut self, _word: &str, _author: &str) -> Result<(), DictError> {
+        Err(DictError::ReadOnly)
+    }
+
+    /// Fait refuser le mot `word`, à la demande de `author`.
+    /// La modification est conservée au prochain chargement du dictionnaire.
+    fn remove_word(&mut self, _word: &str, _author: &str) -> Result<(), DictError> {
+        Err(DictError::ReadOnly)
+    }
+}
+
+/// Raison pour laquelle un mot est refusé par un dictionnaire.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// Le mot contient des caractères ne pouvant pas figurer sur la grille.
+    InvalidCharacters,
+    /// Le mot ne figure pas dans le dictionnaire.
+    Unknown,
+    /// Le mot est exclu, pour la raison donnée.
+    Denied(String),
+    /// Le dictionnaire n'a pas pu être consulté, pour la raison donnée.
+    Unavailable(String),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rejection::InvalidCharacters => write!(f, "caractères invalides"),
+            Rejection::Unknown => write!(f, "mot inconnu"),
+            Rejection::Denied(ref reason) => write!(f, "mot exclu ({})", reason),
+            Rejection::Unavailable(ref reason) => write!(f, "dictionnaire indisponible ({})", reason),
+        }
+    }
+}
+
+impl<D: Dict + ?Sized> Dict for Box<D> {
+    fn contains(&self, word: &str) -> bool {
+        (**self).contains(word)
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        (**self).has_prefix(prefix)
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        (**self).normalizer()
+    }
+
+    fn try_contains(&self, word: &str) -> Result<bool, DictError> {
+        (**self).try_contains(word)
+    }
+
+    fn rejection(&self, word: &str) -> Option<Rejection> {
+        (**self).rejection(word)
+    }
+
+    fn reload(&mut self) -> Result<(), DictError> {
+        (**self).reload()
+    }
+
+    fn add_word(&mut self, word: &str, author: &str) -> Result<(), DictError> {
+        (**self).add_word(word, author)
+    }
+
+    fn remove_word(&mut self, word: &str, author: &str) -> Result<(), DictError> {
+        (**self).remove_word(word, author)
+    }
+}
+
+/// Renvoie le mot d'une ligne de dictionnaire, en ignorant la définition qui peut le suivre
+/// après une tabulation.
+fn word_of_line(line: &str) -> &str {
+    line.split('\t').next().unwrap_or("")
+}
+
+/// Charge le dictionnaire `file`: un dictionnaire compilé, ou une liste de mots à raison d'un par ligne
+/// normalisés par `normalizer`. Un dictionnaire compilé conserve la normalisation de sa compilation.
+pub fn load(file: &str, normalizer: Normalizer) -> Result<Box<dyn Dict>, DictError> {
+    let mut header = vec![];
+    File::open(file)
+        .and_then(|f| f.take(16).read_to_end(&mut header))
+        .map_err(|e| DictError::unreadable_file(file, &e.to_string()))?;
+    if CompiledDict::is_compiled(&header) {
+        Ok(Box::new(CompiledDict::from_file(file)?))
+    } else {
+        Ok(Box::new(TrieDict::from_dictionary_with(file, normalizer)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn load_detects_format() {
+        let file = env::temp_dir().join("boggle_load_test.bin");
+        let file = file.to_str().unwrap();
+        CompiledDict::compile_file("dico_test.txt", file, Normalizer::new()).unwrap();
+        for file in &["dico_test.txt", file] {
+            let dict = load(file, Normalizer::new()).unwrap();
+            assert!(dict.contains("trident"));
+            assert!(dict.has_prefix("il"));
+            assert!(! dict.contains("tri"));
+        }
+        fs::remove_file(file).unwrap();
+        assert!(load("non_existing_dict.txt", Normalizer::new()).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+
+use unidecode::unidecode;
+
+/// Caractères supprimés des mots lorsque la ponctuation est retirée.
+const PUNCTUATION: [char; 3] = ['-', '\'', '’'];
+
+/// Normalisation appliquée aux mots d'un dictionnaire, à l'identique lors de son
+/// chargement et de chaque recherche.
+///
+/// Les étapes sont appliquées dans l'ordre: suppression des accents, passage en minuscules,
+/// suppression des tirets et apostrophes. Un mot contenant encore des caractères ne pouvant
+/// pas figurer sur la grille est ensuite rejeté.
+/// Le passage en minuscules est toujours appliqué: les mots formés sur la grille, par les
+/// joueurs comme par le solveur, ne dépendent pas de la casse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normalizer {
+    accent_stripping: bool,
+    punctuation_removal: bool,
+}
+
+impl Normalizer {
+    /// Normalisation complète: "Aujourd'hui" devient "aujourdhui" et "à" devient "a".
+    pub fn new() -> Normalizer {
+        Normalizer { accent_stripping: true, punctuation_removal: true }
+    }
+
+    /// Active ou non la suppression des accents.
+    /// Sans cette étape, les mots accentués sont rejetés.
+    pub fn with_accent_stripping(mut self, enabled: bool) -> Normalizer {
+        self.accent_stripping = enabled;
+        self
+    }
+
+    /// Active ou non la suppression des tirets et apostrophes.
+    /// Sans cette étape, les mots en contenant sont rejetés.
+    pub fn with_punctuation_removal(mut self, enabled: bool) -> Normalizer {
+        self.punctuation_removal = enabled;
+        self
+    }
+
+    /// Normalise le mot `word`.
+    /// Renvoie `None` s'il contient des caractères ne pouvant pas figurer sur la grille.
+    pub fn normalize<'a>(&self, word: &'a str) -> Option<Cow<'a, str>> {
+        if word.bytes().all(|b| b.is_ascii_lowercase()) {
+            return Some(Cow::Borrowed(word))
+        }
+
+        let mut word = Cow::Borrowed(word);
+        if self.accent_stripping && ! word.is_ascii() {
+            word = Cow::Owned(unidecode(&word));
+        }
+        if word.chars().any(char::is_uppercase) {
+            word = Cow::Owned(word.to_lowercase());
+        }
+        if self.punctuation_removal {
+            word = Cow::Owned(word.replace(&PUNCTUATION[..], ""));
+        }
+
+        if word.chars().all(|c| c.is_ascii_alphabetic()) {
+            Some(word)
+        } else {
+            None
+        }
+    }
+
+    /// Encode les étapes actives sur un octet.
+    pub fn to_flags(self) -> u8 {
+        self.accent_stripping as u8 | (self.punctuation_removal as u8) << 1
+    }
+
+    /// Décode les étapes actives encodées par `to_flags`.
+    pub fn of_flags(flags: u8) -> Normalizer {
+        Normalizer {
+            accent_stripping: flags & 1 != 0,
+            punctuation_removal: flags & 2 != 0,
+        }
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn normalize(normalizer: Normalizer, word: &str) -> Option<String> {
+        normalizer.normalize(word).map(|w| w.into_owned())
+    }
+
+    #[test]
+    fn full_normalization() {
+        let n = Normalizer::new();
+        assert_eq!(normalize(n, "trident"), Some("trident".to_string()));
+        assert_eq!(normalize(n, "TriDent"), Some("trident".to_string()));
+        assert_eq!(normalize(n, "à"), Some("a".to_string()));
+        assert_eq!(normalize(n, "Île"), Some("ile".to_string()));
+        assert_eq!(normalize(n, "aujourd'hui"), Some("aujourdhui".to_string()));
+        assert_eq!(normalize(n, "aujourd’hui"), Some("aujourdhui".to_string()));
+        assert_eq!(normalize(n, "arc-en-ciel"), Some("arcenciel".to_string()));
+        assert_eq!(normalize(n, "cœur"), Some("coeur".to_string()));
+        assert_eq!(normalize(n, ""), Some("".to_string()));
+    }
+
+    #[test]
+    fn non_grid_characters_are_rejected() {
+        let n = Normalizer::new();
+        assert_eq!(normalize(n, "c3po"), None);
+        assert_eq!(normalize(n, "pomme de terre"), None);
+        assert_eq!(normalize(n, "a/b"), None);
+    }
+
+    #[test]
+    fn disabled_steps() {
+        let n = Normalizer::new().with_accent_stripping(false);
+        assert_eq!(normalize(n, "Île"), None);
+        assert_eq!(normalize(n, "ILE"), Some("ile".to_string()));
+
+        let n = Normalizer::new().with_punctuation_removal(false);
+        assert_eq!(normalize(n, "aujourd'hui"), None);
+        assert_eq!(normalize(n, "à"), Some("a".to_string()));
+
+        let n = Normalizer::new().with_accent_stripping(false).with_punctuation_removal(false);
+        assert_eq!(normalize(n, "Paris"), Some("paris".to_string()));
+        assert_eq!(normalize(n, "Île"), None);
+    }
+
+    #[test]
+    fn normalization_is_idempotent() {
+        let n = Normalizer::new();
+        for word in &["Aujourd'hui", "à", "cœur", "arc-en-ciel", "TRIDENT"] {
+            let once = normalize(n, word).unwrap();
+            assert_eq!(normalize(n, &once), Some(once.clone()));
+        }
+    }
+
+    #[test]
+    fn flags() {
+        for flags in 0..4 {
+            assert_eq!(Normalizer::of_flags(flags).to_flags(), flags);
+        }
+        assert_eq!(Normalizer::of_flags(Normalizer::new().to_flags()), Normalizer::new());
+    }
+}
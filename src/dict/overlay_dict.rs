@@ -0,0 +1,368 @@
+use super::{Dict, Normalizer, Rejection};
+use super::super::errors::DictError;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::ErrorKind,
+    ops::Bound,
+};
+
+/// Dictionnaire ajoutant à un autre dictionnaire une liste de mots autorisés et
+/// une liste de mots exclus, chargées depuis des fichiers et rechargeables à tout moment.
+///
+/// Chaque ligne d'une liste contient un mot, éventuellement suivi de `: raison`.
+/// Les lignes vides et celles commençant par '#' sont ignorées. Un mot exclu est
+/// refusé même s'il figure dans la liste des mots autorisés.
+///
+/// Les mots ajoutés ou retirés à la volée sont enregistrés dans ces listes, et sont donc
+/// conservés au prochain démarrage.
+pub struct OverlayDict {
+    inner: Box<dyn Dict>,
+    allow_file: Option<String>,
+    deny_file: Option<String>,
+    /// Mots autorisés, normalisés.
+    allowed: BTreeSet<String>,
+    /// Mots exclus, normalisés, associés à la raison de leur exclusion.
+    denied: HashMap<String, String>,
+}
+
+impl OverlayDict {
+    /// Dictionnaire sans liste, équivalent à `inner`.
+    pub fn new(inner: Box<dyn Dict>) -> OverlayDict {
+        OverlayDict {
+            inner,
+            allow_file: None,
+            deny_file: None,
+            allowed: BTreeSet::new(),
+            denied: HashMap::new(),
+        }
+    }
+
+    /// Définit le fichier des mots autorisés, pris en compte au prochain chargement.
+    pub fn with_allow_list(mut self, file: &str) -> OverlayDict {
+        self.allow_file = Some(file.to_string());
+        self
+    }
+
+    /// Définit le fichier des mots exclus, pris en compte au prochain chargement.
+    pub fn with_deny_list(mut self, file: &str) -> OverlayDict {
+        self.deny_file = Some(file.to_string());
+        self
+    }
+
+    /// Renvoie le nombre de mots autorisés et de mots exclus.
+    pub fn list_sizes(&self) -> (usize, usize) {
+        (self.allowed.len(), self.denied.len())
+    }
+
+    /// Lit la liste `file`: renvoie chaque mot normalisé et la raison éventuelle associée.
+    fn load_list(&self, file: &str) -> Result<Vec<(String, Option<String>)>, DictError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| DictError::unreadable_file(file, &e.to_string()))?;
+
+        content.lines()
+            .enumerate()
+            .filter_map(|(idx, line)| split_entry(line).map(|entry| (idx, entry)))
+            .map(|(idx, (word, reason))| {
+                match self.normalize(word) {
+                    Some(normalized) => Ok((normalized, reason.map(|r| r.to_string()))),
+                    None => Err(DictError::invalid_list_word(file, idx + 1, word)),
+                }
+            })
+            .collect()
+    }
+
+    /// Normalise le mot `word`, en rejetant le mot vide.
+    fn normalize(&self, word: &str) -> Option<String> {
+        self.normalizer().normalize(word)
+            .filter(|w| ! w.is_empty())
+            .map(|w| w.into_owned())
+    }
+
+    /// Ajoute la ligne `entry` à la fin de la liste `file`, créée si elle n'existe pas.
+    fn append_to_list(file: &str, entry: &str) -> Result<(), DictError> {
+        let mut content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(DictError::unreadable_file(file, &e.to_string())),
+        };
+        if ! content.is_empty() && ! content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(entry);
+        content.push('\n');
+        fs::write(file, content).map_err(|e| DictError::unwritable_file(file, &e.to_string()))
+    }
+
+    /// Supprime de la liste `file` les lignes du mot normalisé `word`.
+    /// Les commentaires et les autres mots sont conservés tels quels.
+    fn remove_from_list(&self, file: &str, word: &str) -> Result<(), DictError> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| DictError::unreadable_file(file, &e.to_string()))?;
+        let content = content.lines()
+            .filter(|line| split_entry(line)
+                .and_then(|(w, _)| self.normalize(w))
+                .is_none_or(|w| w != word))
+            .fold(String::new(), |acc, line| acc + line + "\n");
+        fs::write(file, content).map_err(|e| DictError::unwritable_file(file, &e.to_string()))
+    }
+}
+
+/// Découpe une ligne de liste en un mot et une raison éventuelle.
+/// Renvoie `None` pour une ligne vide ou un commentaire.
+fn split_entry(line: &str) -> Option<(&str, Option<&str>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None
+    }
+    match line.find(':') {
+        Some(pos) => {
+            let reason = line[pos + 1..].trim();
+            Some((line[..pos].trim(), Some(reason).filter(|r| ! r.is_empty())))
+        },
+        None => Some((line, None)),
+    }
+}
+
+impl Dict for OverlayDict {
+    fn contains(&self, word: &str) -> bool {
+        match self.normalizer().normalize(word) {
+            Some(word) => ! self.denied.contains_key(word.as_ref())
+                && (self.allowed.contains(word.as_ref()) || self.inner.contains(&word)),
+            None => false,
+        }
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        if self.inner.has_prefix(prefix) {
+            return true
+        }
+        match self.normalizer().normalize(prefix) {
+            Some(prefix) => self.allowed
+                .range::<str, _>((Bound::Included(prefix.as_ref()), Bound::Unbounded))
+                .next()
+                .is_some_and(|w| w.starts_with(prefix.as_ref())),
+            None => false,
+        }
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        self.inner.normalizer()
+    }
+
+    fn try_contains(&self, word: &str) -> Result<bool, DictError> {
+        let word = match self.normalizer().normalize(word) {
+            Some(word) => word,
+            None => return Ok(false),
+        };
+        if self.denied.contains_key(word.as_ref()) {
+            Ok(false)
+        } else if self.allowed.contains(word.as_ref()) {
+            Ok(true)
+        } else {
+            self.inner.try_contains(&word)
+        }
+    }
+
+    fn rejection(&self, word: &str) -> Option<Rejection> {
+        let word = match self.normalizer().normalize(word) {
+            Some(word) => word,
+            None => return Some(Rejection::InvalidCharacters),
+        };
+        if let Some(reason) = self.denied.get(word.as_ref()) {
+            Some(Rejection::Denied(reason.clone()))
+        } else if self.allowed.contains(word.as_ref()) {
+            None
+        } else {
+            self.inner.rejection(&word)
+        }
+    }
+
+    /// Recharge les deux listes. En cas d'erreur, les listes précédentes sont conservées.
+    fn reload(&mut self) -> Result<(), DictError> {
+        let allowed = match self.allow_file {
+            Some(ref file) => self.load_list(file)?.into_iter().map(|(w, _)| w).collect(),
+            None => BTreeSet::new(),
+        };
+        let denied = match self.deny_file {
+            Some(ref file) => self.load_list(file)?.into_iter()
+                .map(|(w, reason)| (w, reason.unwrap_or_else(|| format!("liste d'exclusion {}", file))))
+                .collect(),
+            None => HashMap::new(),
+        };
+        self.allowed = allowed;
+        self.denied = denied;
+        self.inner.reload()
+    }
+
+    /// Retire `word` des mots exclus, puis l'ajoute aux mots autorisés s'il est encore refusé.
+    /// Renvoie une erreur, sans rien enregistrer, si le dictionnaire n'a pas pu être consulté.
+    fn add_word(&mut self, word: &str, author: &str) -> Result<(), DictError> {
+        let word = self.normalize(word).ok_or_else(|| DictError::invalid_word(word))?;
+        if self.denied.contains_key(&word) {
+            let file = self.deny_file.clone().ok_or(DictError::ReadOnly)?;
+            self.remove_from_list(&file, &word)?;
+            self.denied.remove(&word);
+        }
+        if ! self.try_contains(&word)? {
+            let file = self.allow_file.clone().ok_or(DictError::ReadOnly)?;
+            OverlayDict::append_to_list(&file, &format!("{}: ajouté par {}", word, author))?;
+            self.allowed.insert(word);
+        }
+        Ok(())
+    }
+
+    /// Retire `word` des mots autorisés, puis l'ajoute aux mots exclus s'il est encore accepté.
+    /// Renvoie une erreur, sans rien enregistrer, si le dictionnaire n'a pas pu être consulté.
+    fn remove_word(&mut self, word: &str, author: &str) -> Result<(), DictError> {
+        let word = self.normalize(word).ok_or_else(|| DictError::invalid_word(word))?;
+        if self.allowed.contains(&word) {
+            let file = self.allow_file.clone().ok_or(DictError::ReadOnly)?;
+            self.remove_from_list(&file, &word)?;
+            self.allowed.remove(&word);
+        }
+        if self.try_contains(&word)? {
+            let file = self.deny_file.clone().ok_or(DictError::ReadOnly)?;
+            let reason = format!("retiré par {}", author);
+            OverlayDict::append_to_list(&file, &format!("{}: {}", word, reason))?;
+            self.denied.insert(word, reason);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::{HttpDict, TrieDict};
+    use std::{env, path::{Path, PathBuf}, time::Duration};
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let file = env::temp_dir().join(name);
+        fs::write(&file, content).unwrap();
+        file
+    }
+
+    fn create_test_dict(allow: &Path, deny: &Path) -> OverlayDict {
+        let inner = TrieDict::from_words(vec!["trident", "ile", "merde", "paris"]);
+        let mut dict = OverlayDict::new(Box::new(inner))
+            .with_allow_list(allow.to_str().unwrap())
+            .with_deny_list(deny.to_str().unwrap());
+        dict.reload().unwrap();
+        dict
+    }
+
+    #[test]
+    fn overlays() {
+        let allow = temp_file("boggle_overlay_allow.txt", "# Mots autorisés\nLyon\nmerde\n");
+        let deny = temp_file("boggle_overlay_deny.txt", "merde: grossier\n\nParis\n");
+        let dict = create_test_dict(&allow, &deny);
+
+        assert_eq!(dict.list_sizes(), (2, 2));
+        assert!(dict.contains("trident"));
+        assert!(dict.contains("LYON"));
+        assert!(dict.has_prefix("ly"));
+        assert!(! dict.has_prefix("lx"));
+        assert!(! dict.contains("merde"));
+        assert!(! dict.contains("paris"));
+        assert_eq!(dict.rejection("Merde"), Some(Rejection::Denied("grossier".to_string())));
+        let reason = format!("liste d'exclusion {}", deny.to_str().unwrap());
+        assert_eq!(dict.rejection("paris"), Some(Rejection::Denied(reason)));
+        assert_eq!(dict.rejection("tridents"), Some(Rejection::Unknown));
+        assert_eq!(dict.rejection("c3po"), Some(Rejection::InvalidCharacters));
+
+        fs::remove_file(allow).unwrap();
+        fs::remove_file(deny).unwrap();
+    }
+
+    #[test]
+    fn reload() {
+        let allow = temp_file("boggle_reload_allow.txt", "lyon\n");
+        let deny = temp_file("boggle_reload_deny.txt", "ile\n");
+        let mut dict = create_test_dict(&allow, &deny);
+        assert!(! dict.contains("ile"));
+
+        fs::write(&deny, "trident\n").unwrap();
+        dict.reload().unwrap();
+        assert!(dict.contains("ile"));
+        assert!(! dict.contains("trident"));
+
+        fs::write(&allow, "lyon\nc3po\n").unwrap();
+        match dict.reload() {
+            Err(DictError::InvalidListWord { line: 2, .. }) => (),
+            _ => panic!("c3po cannot be formed on the grid !")
+        }
+        assert!(dict.contains("lyon"));
+        assert!(! dict.contains("trident"));
+
+        fs::remove_file(&deny).unwrap();
+        assert!(dict.reload().is_err());
+
+        fs::remove_file(allow).unwrap();
+    }
+
+    #[test]
+    fn add_and_remove_words() {
+        let allow = temp_file("boggle_edit_allow.txt", "# Noms propres\nlyon\n");
+        let deny = temp_file("boggle_edit_deny.txt", "merde: grossier\n");
+        let mut dict = create_test_dict(&allow, &deny);
+
+        dict.add_word("Marseille", "bob").unwrap();
+        dict.add_word("merde", "bob").unwrap();
+        dict.remove_word("lyon", "alice").unwrap();
+        dict.remove_word("trident", "alice").unwrap();
+        dict.add_word("ile", "bob").unwrap();
+        assert!(dict.contains("marseille"));
+        assert!(dict.contains("merde"));
+        assert!(! dict.contains("lyon"));
+        assert_eq!(dict.rejection("trident"), Some(Rejection::Denied("retiré par alice".to_string())));
+
+        assert_eq!(fs::read_to_string(&allow).unwrap(), "# Noms propres\nmarseille: ajouté par bob\n");
+        assert_eq!(fs::read_to_string(&deny).unwrap(), "trident: retiré par alice\n");
+        // Les modifications sont conservées au prochain chargement.
+        let reloaded = create_test_dict(&allow, &deny);
+        assert!(reloaded.contains("marseille"));
+        assert!(! reloaded.contains("trident"));
+
+        match dict.add_word("c3po", "bob") {
+            Err(DictError::InvalidWord { .. }) => (),
+            _ => panic!("c3po cannot be formed on the grid !")
+        }
+        fs::remove_file(allow).unwrap();
+        fs::remove_file(deny).unwrap();
+    }
+
+    #[test]
+    fn missing_lists_are_read_only() {
+        let mut dict = OverlayDict::new(Box::new(TrieDict::from_words(vec!["trident"])));
+        match dict.add_word("lyon", "bob") {
+            Err(DictError::ReadOnly) => (),
+            _ => panic!("No allow list is defined !")
+        }
+        assert!(dict.add_word("trident", "bob").is_ok());
+        assert!(dict.remove_word("lyon", "bob").is_ok());
+    }
+
+    #[test]
+    fn unavailable_dict_is_not_changed() {
+        let allow = temp_file("boggle_overlay_unavailable_allow.txt", "");
+        let deny = temp_file("boggle_overlay_unavailable_deny.txt", "");
+        let inner = HttpDict::new("http://127.0.0.1:1/").unwrap().with_timeout(Duration::from_millis(100));
+        let mut dict = OverlayDict::new(Box::new(inner))
+            .with_allow_list(allow.to_str().unwrap())
+            .with_deny_list(deny.to_str().unwrap());
+        dict.reload().unwrap();
+
+        match dict.remove_word("trident", "admin") {
+            Err(DictError::Unreachable {..}) => (),
+            _ => panic!("The dictionary could not be checked !")
+        }
+        assert!(dict.add_word("trident", "admin").is_err());
+        assert_eq!(dict.list_sizes(), (0, 0));
+        assert_eq!(fs::read_to_string(&deny).unwrap(), "");
+
+        fs::remove_file(allow).unwrap();
+        fs::remove_file(deny).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use super::{Dict, Normalizer, word_of_line};
+
+use std::fs;
+
+/// Noeud du trie: ses fils, triés par lettre, et un indicateur de fin de mot.
+#[derive(Default)]
+struct Node {
+    children: Vec<(char, usize)>,
+    terminal: bool,
+}
+
+/// Dictionnaire stocké sous forme de trie, permettant de tester efficacement
+/// si un préfixe peut être prolongé en un mot.
+pub struct TrieDict {
+    nodes: Vec<Node>,
+    normalizer: Normalizer,
+}
+
+impl TrieDict {
+    #[cfg(test)]
+    pub fn from_dictionary(file: &str) -> TrieDict {
+        TrieDict::from_dictionary_with(file, Normalizer::new())
+    }
+
+    /// Charge la liste de mots `file` en normalisant chaque mot avec `normalizer`.
+    pub fn from_dictionary_with(file: &str, normalizer: Normalizer) -> TrieDict {
+        let content = fs::read_to_string(file).unwrap_or_else(|_| panic!("Cannot open file: {}", file));
+        TrieDict::from_text(&content, normalizer)
+    }
+
+    /// Construit le dictionnaire des mots de `content`, un mot par ligne,
+    /// éventuellement suivi de sa définition.
+    pub fn from_text(content: &str, normalizer: Normalizer) -> TrieDict {
+        TrieDict::from_words_with(content.lines().map(word_of_line), normalizer)
+    }
+
+    #[cfg(test)]
+    pub fn from_words<S: AsRef<str>, I: IntoIterator<Item = S>>(words: I) -> TrieDict {
+        TrieDict::from_words_with(words, Normalizer::new())
+    }
+
+    /// Construit le dictionnaire des mots `words`, normalisés par `normalizer`.
+    /// Les mots rejetés par `normalizer` sont ignorés.
+    fn from_words_with<S: AsRef<str>, I: IntoIterator<Item = S>>(words: I, normalizer: Normalizer) -> TrieDict {
+        let mut dict = TrieDict { nodes: vec![Node::default()], normalizer };
+        for word in words {
+            if let Some(word) = normalizer.normalize(word.as_ref()).filter(|w| ! w.is_empty()) {
+                dict.insert(&word);
+            }
+        }
+        dict
+    }
+
+    fn insert(&mut self, word: &str) {
+        let mut current = 0;
+        for c in word.chars() {
+            current = match self.child(current, c) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    let children = &mut self.nodes[current].children;
+                    let pos = children.binary_search_by_key(&c, |&(l, _)| l).unwrap_err();
+                    children.insert(pos, (c, child));
+                    child
+                }
+            };
+        }
+        self.nodes[current].terminal = true;
+    }
+
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children.binary_search_by_key(&c, |&(l, _)| l).ok()
+            .map(|pos| children[pos].1)
+    }
+
+    /// Renvoie les fils du noeud `node`, triés par lettre.
+    pub(super) fn children(&self, node: usize) -> &[(char, usize)] {
+        &self.nodes[node].children
+    }
+
+    /// Renvoie true si le noeud `node` termine un mot.
+    pub(super) fn is_terminal(&self, node: usize) -> bool {
+        self.nodes[node].terminal
+    }
+
+    /// Renvoie le noeud atteint en suivant les lettres de `prefix`, s'il existe.
+    fn walk(&self, prefix: &str) -> Option<usize> {
+        self.normalizer.normalize(prefix)?.chars()
+            .try_fold(0, |node, c| self.child(node, c))
+    }
+}
+
+impl Dict for TrieDict {
+    fn contains(&self, word: &str) -> bool {
+        self.walk(word).is_some_and(|node| self.nodes[node].terminal)
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        self.walk(prefix).is_some()
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_test_dict() -> TrieDict {
+        TrieDict::from_words(vec!["trident".to_string(), "tri".to_string(), "ile".to_string()])
+    }
+
+    #[test]
+    fn contains_ok() {
+        let dict = create_test_dict();
+        assert!(dict.contains("trident"));
+        assert!(dict.contains("TRI"));
+        assert!(! dict.contains("trid"));
+        assert!(! dict.contains("tridents"));
+    }
+
+    #[test]
+    fn has_prefix_ok() {
+        let dict = create_test_dict();
+        assert!(dict.has_prefix(""));
+        assert!(dict.has_prefix("trid"));
+        assert!(dict.has_prefix("Il"));
+        assert!(dict.has_prefix("trident"));
+        assert!(! dict.has_prefix("tridents"));
+        assert!(! dict.has_prefix("x"));
+    }
+
+    #[test]
+    fn from_dictionary_ok() {
+        let dict = TrieDict::from_dictionary("dico_test.txt");
+        assert!(dict.contains("trident"));
+        assert!(dict.contains("ile"));
+    }
+
+    #[test]
+    fn words_are_normalized() {
+        let dict = TrieDict::from_text("Île\naujourd'hui\nà\nc3po\n\n", Normalizer::new());
+        assert!(dict.contains("ile"));
+        assert!(dict.contains("ÎLE"));
+        assert!(dict.contains("aujourdhui"));
+        assert!(dict.contains("Aujourd’hui"));
+        assert!(dict.contains("a"));
+        assert!(! dict.contains("c3po"));
+        assert!(! dict.has_prefix("c3"));
+        assert!(! dict.contains(""));
+    }
+
+    #[test]
+    fn normalization_is_configurable() {
+        let normalizer = Normalizer::new().with_accent_stripping(false);
+        let dict = TrieDict::from_text("Île\nParis\nlyon", normalizer);
+        assert!(! dict.contains("ile"));
+        assert!(! dict.contains("Île"));
+        assert!(dict.contains("paris"));
+        assert!(dict.contains("Lyon"));
+    }
+
+    #[test]
+    fn definitions_are_ignored() {
+        let dict = TrieDict::from_text("trident\tFourche à trois dents.\nile", Normalizer::new());
+        assert!(dict.contains("trident"));
+        assert!(dict.contains("ile"));
+        assert!(! dict.has_prefix("tridentf"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_panics_on_invalid_dict_file() {
+        TrieDict::from_dictionary("non_existing_dict.txt");
+    }
+}
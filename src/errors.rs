@@ -1,4 +1,4 @@
-
+use super::dict::Rejection;
 
 #[derive(Debug, Clone, Fail)]
 pub enum ServerError {
@@ -11,8 +11,8 @@ pub enum ServerError {
     #[fail(display = "Requête invalide: {}.", request)]
     BadRequest { request: String },
 
-    #[fail(display = "Le mot {} n'existe pas.", word)]
-    NonExistingWord { word: String },
+    #[fail(display = "Le mot {} est refusé: {}.", word, reason)]
+    RejectedWord { word: String, reason: String },
 
     #[fail(display = "Le mot {} a déjà ete joué.", word)]
     AlreadyPlayed { word: String, immediate: bool },
@@ -23,6 +23,42 @@ pub enum ServerError {
     #[fail(display = "La trajectoire {} ne correspond pas au mot {}.", trajectory, word)]
     NoMatch { word: String, trajectory: String },
 
+    #[fail(display = "Il ne reste aucun mot sur lequel donner un indice à {}.", username)]
+    NoHint { username: String },
+
+    #[fail(display = "Mot de passe administrateur invalide pour {}.", username)]
+    BadAdminPassword { username: String },
+
+    #[fail(display = "La requête <{}> est réservée aux administrateurs, {} n'en est pas un.", request, username)]
+    NotAdmin { username: String, request: String },
+
+    #[fail(display = "Le rechargement du dictionnaire demandé par {} a échoué: {}", username, err)]
+    ReloadFailed { username: String, err: String },
+
+    #[fail(display = "La modification du mot {} demandée par {} a échoué: {}", word, username, err)]
+    DictChangeFailed { username: String, word: String, err: String },
+
+    #[fail(display = "Aucune définition du mot {} n'est disponible.", word)]
+    NoDefinition { word: String },
+
+    #[fail(display = "La requête <{}> n'est possible que pendant la pause entre deux tours.", request)]
+    TurnRunning { request: String },
+
+    #[fail(display = "{} ne peut pas contester le mot {}.", username, word)]
+    CannotChallenge { username: String, word: String },
+
+    #[fail(display = "{} a déjà contesté le mot {} lors de ce tour.", username, word)]
+    AlreadyChallenged { username: String, word: String },
+
+    #[fail(display = "Aucune contestation du mot {} par {} n'est en cours.", word, challenger)]
+    NoChallenge { challenger: String, word: String },
+
+    #[fail(display = "{} ne peut pas voter sur la contestation du mot {}.", username, word)]
+    NotAVoter { username: String, word: String },
+
+    #[fail(display = "{} a déjà voté sur la contestation du mot {}.", username, word)]
+    AlreadyVoted { username: String, word: String },
+
     #[fail(display = "La requête <{}> ne peut être soumise par un utilisateur non connecté.", request)]
     UnauthorizedRequest { request: String },
 
@@ -49,9 +85,10 @@ impl ServerError {
         }
     }
 
-    pub fn non_existing_word(word: &str) -> ServerError {
-        ServerError::NonExistingWord {
-            word: word.to_string()
+    pub fn rejected_word(word: &str, reason: &Rejection) -> ServerError {
+        ServerError::RejectedWord {
+            word: word.to_string(),
+            reason: reason.to_string()
         }
     }
 
@@ -75,6 +112,87 @@ impl ServerError {
         }
     }
 
+    pub fn no_hint(username: &str) -> ServerError {
+        ServerError::NoHint {
+            username: username.to_string()
+        }
+    }
+
+    pub fn bad_admin_password(username: &str) -> ServerError {
+        ServerError::BadAdminPassword {
+            username: username.to_string()
+        }
+    }
+
+    pub fn not_admin(username: &str, request: &str) -> ServerError {
+        ServerError::NotAdmin {
+            username: username.to_string(),
+            request: request.to_string()
+        }
+    }
+
+    pub fn reload_failed(username: &str, err: &DictError) -> ServerError {
+        ServerError::ReloadFailed {
+            username: username.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn dict_change_failed(username: &str, word: &str, err: &DictError) -> ServerError {
+        ServerError::DictChangeFailed {
+            username: username.to_string(),
+            word: word.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn no_definition(word: &str) -> ServerError {
+        ServerError::NoDefinition {
+            word: word.to_string()
+        }
+    }
+
+    pub fn turn_running(request: &str) -> ServerError {
+        ServerError::TurnRunning {
+            request: request.to_string()
+        }
+    }
+
+    pub fn cannot_challenge(username: &str, word: &str) -> ServerError {
+        ServerError::CannotChallenge {
+            username: username.to_string(),
+            word: word.to_string()
+        }
+    }
+
+    pub fn already_challenged(username: &str, word: &str) -> ServerError {
+        ServerError::AlreadyChallenged {
+            username: username.to_string(),
+            word: word.to_string()
+        }
+    }
+
+    pub fn no_challenge(challenger: &str, word: &str) -> ServerError {
+        ServerError::NoChallenge {
+            challenger: challenger.to_string(),
+            word: word.to_string()
+        }
+    }
+
+    pub fn not_a_voter(username: &str, word: &str) -> ServerError {
+        ServerError::NotAVoter {
+            username: username.to_string(),
+            word: word.to_string()
+        }
+    }
+
+    pub fn already_voted(username: &str, word: &str) -> ServerError {
+        ServerError::AlreadyVoted {
+            username: username.to_string(),
+            word: word.to_string()
+        }
+    }
+
     pub fn unauthorized_request(request: &str) -> ServerError {
         ServerError::UnauthorizedRequest {
             request: request.to_string()
@@ -89,4 +207,313 @@ impl ServerError {
             err: Box::new(err)
         }
     }
-}
\ No newline at end of file
+
+    /// Renvoie le code identifiant le type de l'erreur dans les réponses aux clients.
+    /// L'échec d'un message privé a le code de sa cause.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ServerError::ExistingUser { .. } => "PSEUDO_PRIS",
+            ServerError::NonExistingUser { .. } => "UTILISATEUR_INCONNU",
+            ServerError::BadRequest { .. } => "REQUETE_INVALIDE",
+            ServerError::RejectedWord { .. } => "MOT_REFUSE",
+            ServerError::AlreadyPlayed { .. } => "MOT_DEJA_JOUE",
+            ServerError::BadTrajectory { .. } => "TRAJECTOIRE_INVALIDE",
+            ServerError::NoMatch { .. } => "TRAJECTOIRE_INCORRECTE",
+            ServerError::NoHint { .. } => "AUCUN_INDICE",
+            ServerError::BadAdminPassword { .. } => "MDP_INVALIDE",
+            ServerError::NotAdmin { .. } => "NON_ADMIN",
+            ServerError::ReloadFailed { .. } => "RECHARGEMENT_ECHOUE",
+            ServerError::DictChangeFailed { .. } => "MODIFICATION_ECHOUEE",
+            ServerError::NoDefinition { .. } => "AUCUNE_DEFINITION",
+            ServerError::TurnRunning { .. } => "TOUR_EN_COURS",
+            ServerError::CannotChallenge { .. } => "CONTESTATION_IMPOSSIBLE",
+            ServerError::AlreadyChallenged { .. } => "DEJA_CONTESTE",
+            ServerError::NoChallenge { .. } => "AUCUNE_CONTESTATION",
+            ServerError::NotAVoter { .. } => "VOTE_INTERDIT",
+            ServerError::AlreadyVoted { .. } => "DEJA_VOTE",
+            ServerError::UnauthorizedRequest { .. } => "NON_CONNECTE",
+            ServerError::InvalidChat { ref err, .. } => err.code(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fail)]
+pub enum DiceError {
+    #[fail(display = "Impossible de lire le fichier de dés {}: {}.", file, err)]
+    UnreadableFile { file: String, err: String },
+
+    #[fail(display = "Le jeu contient {} dés au lieu de {}.", found, expected)]
+    WrongDiceCount { found: usize, expected: usize },
+
+    #[fail(display = "Ligne {}: un dé doit avoir 6 faces, {} trouvées.", line, found)]
+    WrongFaceCount { line: usize, found: usize },
+
+    #[fail(display = "Ligne {}: un dé ne peut pas avoir de face vide.", line)]
+    EmptyFace { line: usize },
+
+    #[fail(display = "Ligne {}: la face <{}> ne doit contenir que des lettres.", line, face)]
+    InvalidFace { line: usize, face: String },
+}
+
+impl DiceError {
+    pub fn unreadable_file(file: &str, err: &str) -> DiceError {
+        DiceError::UnreadableFile {
+            file: file.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn wrong_dice_count(found: usize, size: usize) -> DiceError {
+        DiceError::WrongDiceCount {
+            found,
+            expected: size * size
+        }
+    }
+
+    pub fn wrong_face_count(line: usize, found: usize) -> DiceError {
+        DiceError::WrongFaceCount { line, found }
+    }
+
+    pub fn empty_face(line: usize) -> DiceError {
+        DiceError::EmptyFace { line }
+    }
+
+    pub fn invalid_face(line: usize, face: &str) -> DiceError {
+        DiceError::InvalidFace {
+            line,
+            face: face.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fail)]
+pub enum CatalogueError {
+    #[fail(display = "Impossible de lire le catalogue {}: {}.", file, err)]
+    UnreadableFile { file: String, err: String },
+
+    #[fail(display = "Le catalogue ne contient aucune grille.")]
+    Empty,
+
+    #[fail(display = "Ligne {}: une grille doit commencer par [grille].", line)]
+    MissingHeader { line: usize },
+
+    #[fail(display = "Ligne {}: une ligne doit être de la forme <clé: valeur>.", line)]
+    InvalidLine { line: usize },
+
+    #[fail(display = "Ligne {}: clé inconnue <{}>.", line, key)]
+    UnknownKey { line: usize, key: String },
+
+    #[fail(display = "Ligne {}: la clé <{}> est déjà définie pour cette grille.", line, key)]
+    DuplicateKey { line: usize, key: String },
+
+    #[fail(display = "Ligne {}: la graine <{}> doit être un nombre entier positif.", line, seed)]
+    InvalidSeed { line: usize, seed: String },
+
+    #[fail(display = "Ligne {}: <{}> n'est pas une grille de {} cases.", line, grid, nb_cells)]
+    InvalidGrid { line: usize, grid: String, nb_cells: usize },
+
+    #[fail(display = "Ligne {}: <{}> n'est pas une disposition de bonus de {} cases.", line, bonus, nb_cells)]
+    InvalidBonus { line: usize, bonus: String, nb_cells: usize },
+
+    #[fail(display = "Ligne {}: la grille doit avoir une clé grille ou graine.", line)]
+    MissingGrid { line: usize },
+
+    #[fail(display = "Ligne {}: la grille ne correspond pas à sa graine.", line)]
+    SeedMismatch { line: usize },
+
+    #[fail(display = "Ligne {}: le mot {} ne peut pas être formé sur la grille.", line, word)]
+    NotASolution { line: usize, word: String },
+}
+
+impl CatalogueError {
+    pub fn unreadable_file(file: &str, err: &str) -> CatalogueError {
+        CatalogueError::UnreadableFile {
+            file: file.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn unknown_key(line: usize, key: &str) -> CatalogueError {
+        CatalogueError::UnknownKey {
+            line,
+            key: key.to_string()
+        }
+    }
+
+    pub fn duplicate_key(line: usize, key: &str) -> CatalogueError {
+        CatalogueError::DuplicateKey {
+            line,
+            key: key.to_string()
+        }
+    }
+
+    pub fn invalid_seed(line: usize, seed: &str) -> CatalogueError {
+        CatalogueError::InvalidSeed {
+            line,
+            seed: seed.to_string()
+        }
+    }
+
+    pub fn invalid_grid(line: usize, grid: &str, size: usize) -> CatalogueError {
+        CatalogueError::InvalidGrid {
+            line,
+            grid: grid.to_string(),
+            nb_cells: size * size
+        }
+    }
+
+    pub fn invalid_bonus(line: usize, bonus: &str, size: usize) -> CatalogueError {
+        CatalogueError::InvalidBonus {
+            line,
+            bonus: bonus.to_string(),
+            nb_cells: size * size
+        }
+    }
+
+    pub fn not_a_solution(line: usize, word: &str) -> CatalogueError {
+        CatalogueError::NotASolution {
+            line,
+            word: word.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fail)]
+pub enum ScoringError {
+    #[fail(display = "Impossible de lire le barème {}: {}.", file, err)]
+    UnreadableFile { file: String, err: String },
+
+    #[fail(display = "Le barème ne contient aucune règle.")]
+    Empty,
+
+    #[fail(display = "Ligne {}: une ligne doit être de la forme <clé: points>.", line)]
+    InvalidLine { line: usize },
+
+    #[fail(display = "Ligne {}: la clé <{}> doit être une longueur de mot ou une lettre.", line, key)]
+    InvalidKey { line: usize, key: String },
+
+    #[fail(display = "Ligne {}: la clé <{}> est déjà définie.", line, key)]
+    DuplicateKey { line: usize, key: String },
+
+    #[fail(display = "Ligne {}: les points <{}> doivent être un nombre entier positif.", line, points)]
+    InvalidPoints { line: usize, points: String },
+}
+
+impl ScoringError {
+    pub fn unreadable_file(file: &str, err: &str) -> ScoringError {
+        ScoringError::UnreadableFile {
+            file: file.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn invalid_key(line: usize, key: &str) -> ScoringError {
+        ScoringError::InvalidKey {
+            line,
+            key: key.to_string()
+        }
+    }
+
+    pub fn duplicate_key(line: usize, key: &str) -> ScoringError {
+        ScoringError::DuplicateKey {
+            line,
+            key: key.to_string()
+        }
+    }
+
+    pub fn invalid_points(line: usize, points: &str) -> ScoringError {
+        ScoringError::InvalidPoints {
+            line,
+            points: points.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fail)]
+pub enum DictError {
+    #[fail(display = "Impossible de lire le dictionnaire {}: {}.", file, err)]
+    UnreadableFile { file: String, err: String },
+
+    #[fail(display = "Impossible d'écrire le dictionnaire {}: {}.", file, err)]
+    UnwritableFile { file: String, err: String },
+
+    #[fail(display = "Le fichier n'est pas un dictionnaire compilé valide.")]
+    InvalidFormat,
+
+    #[fail(display = "La version {} du format de dictionnaire compilé n'est pas supportée.", version)]
+    UnsupportedVersion { version: u8 },
+
+    #[fail(display = "La lettre {} ne peut pas être compilée: seuls les caractères ASCII sont supportés.", letter)]
+    NonAsciiLetter { letter: char },
+
+    #[fail(display = "Le dictionnaire est trop volumineux pour être compilé.")]
+    TooLarge,
+
+    #[fail(display = "{}, ligne {}: le mot <{}> ne peut pas être formé sur une grille.", file, line, word)]
+    InvalidListWord { file: String, line: usize, word: String },
+
+    #[fail(display = "Le mot <{}> ne peut pas être formé sur une grille.", word)]
+    InvalidWord { word: String },
+
+    #[fail(display = "Le dictionnaire ne peut pas être modifié: aucune liste de mots autorisés ou exclus n'est définie.")]
+    ReadOnly,
+
+    #[fail(display = "L'adresse <{}> n'est pas de la forme http://hôte[:port][/chemin].", url)]
+    InvalidUrl { url: String },
+
+    #[fail(display = "Le service de dictionnaire {} est injoignable: {}.", url, err)]
+    Unreachable { url: String, err: String },
+
+    #[fail(display = "Réponse inattendue du service de dictionnaire {}: <{}>.", url, response)]
+    UnexpectedResponse { url: String, response: String },
+}
+
+impl DictError {
+    pub fn unreadable_file(file: &str, err: &str) -> DictError {
+        DictError::UnreadableFile {
+            file: file.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn unwritable_file(file: &str, err: &str) -> DictError {
+        DictError::UnwritableFile {
+            file: file.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn invalid_word(word: &str) -> DictError {
+        DictError::InvalidWord {
+            word: word.to_string()
+        }
+    }
+
+    pub fn invalid_url(url: &str) -> DictError {
+        DictError::InvalidUrl {
+            url: url.to_string()
+        }
+    }
+
+    pub fn unreachable(url: &str, err: &str) -> DictError {
+        DictError::Unreachable {
+            url: url.to_string(),
+            err: err.to_string()
+        }
+    }
+
+    pub fn unexpected_response(url: &str, response: &str) -> DictError {
+        DictError::UnexpectedResponse {
+            url: url.to_string(),
+            response: response.to_string()
+        }
+    }
+
+    pub fn invalid_list_word(file: &str, line: usize, word: &str) -> DictError {
+        DictError::InvalidListWord {
+            file: file.to_string(),
+            line,
+            word: word.to_string()
+        }
+    }
+}
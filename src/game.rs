@@ -1,54 +1,67 @@
 use super::{
-    board::Board,
+    board::{Board, GridStats, Hint, Verdict},
     errors::ServerError,
     players::Players,
-    dict::Dict
+    dict::{Definitions, Dict, Rejection},
+    errors::DictError,
+    protocol::{MessageSink, ServerMessage},
 };
 
-use std::{
-    io::{Write},
-    sync::{RwLock, Mutex, Condvar},
-};
+use std::sync::{RwLock, Mutex};
 
-pub struct Game<T: Write + Clone> {
+pub struct Game<T: MessageSink + Clone> {
     players: RwLock<Players<T>>,
     board: RwLock<Board>,
-    dict: RwLock<Box<Dict>>,
+    dict: RwLock<Box<dyn Dict>>,
+    definitions: Option<Definitions>,
     turn_running: Mutex<bool>,
-    turn_cond: Condvar,
+    /// Flux des joueurs connectés entre deux tours, accueillis au début du tour suivant.
+    awaiting_welcome: Mutex<Vec<T>>,
 }
 
-impl<T: Write + Clone> Game<T> {
+impl<T: MessageSink + Clone> Game<T> {
     pub fn new<U: Dict + 'static>(players: Players<T>, board: Board, dict: U) -> Self {
         Game {
             players: RwLock::new(players),
             board: RwLock::new(board),
             dict: RwLock::new(Box::new(dict)),
+            definitions: None,
             turn_running: Mutex::new(false),
-            turn_cond: Condvar::new(),
+            awaiting_welcome: Mutex::new(Vec::new()),
         }
     }
 
+    /// Définit les définitions des mots, envoyées sur demande et à la fin de chaque tour.
+    pub fn with_definitions(mut self, definitions: Definitions) -> Self {
+        self.definitions = Some(definitions);
+        self
+    }
+
     /// Enregistre l'utilisateur `username`.
-    /// En cas de succès, le message de bienvenue sera écrit sur le flux `steam`.
+    /// En cas de succès, le message de bienvenue sera écrit sur le flux `steam`, dès maintenant
+    /// si un tour est en cours et sinon au début du tour suivant.
     /// REnvoie une erreur si l'utilisateur éxiste déjà.
     pub fn login(&self, username: &str, mut stream: T) -> Result<(), ServerError> {
         self.board.write().unwrap().add_user(username);
         let mut guard = self.players.write().unwrap();
         let res = guard.login(username, stream.clone());
         drop(guard);
-        res.map(|_| self.welcome(&mut stream))
+        res.map(|_| {
+            let running = self.turn_running.lock().unwrap();
+            if *running {
+                self.welcome(&mut stream)
+            } else {
+                self.awaiting_welcome.lock().unwrap().push(stream)
+            }
+        })
     }
 
     /// Ecrit le message de bienvenue sur le flux `stream`.
     fn welcome(&self, stream: &mut T) {
-        let mut running = self.turn_running.lock().unwrap();
-        while ! *running {
-            running = self.turn_cond.wait(running).unwrap();
-        }
         let board = self.board.read().unwrap();
-        let welcome_str = board.welcome_str();
-        stream.write(welcome_str.as_bytes()).unwrap();
+        if let Err(e) = stream.send(&board.welcome_message()) {
+            eprintln!("Error while welcoming user: {}", e)
+        }
     }
 
     /// Supprime l'utilisateur `username`.
@@ -60,27 +73,35 @@ impl<T: Write + Clone> Game<T> {
     }
 
     /// Démarre une session de jeu.
-    pub fn start_session(&self) {
+    /// Renvoie la graine de la session.
+    pub fn start_session(&self) -> u64 {
         let mut players = self.players.write().unwrap();
-        players.broadcast_message("SESSION/\n");
+        players.broadcast_message(&ServerMessage::Session);
+        self.board.read().unwrap().seed()
     }
 
     /// Met fin à la sessionde jeu courante.
     pub fn end_session(&self) {
-        let msg = self.board.write().unwrap().scores_str();
+        let scores = self.board.write().unwrap().scores();
         let mut players = self.players.write().unwrap();
-        players.broadcast_message(&format!("VAINQUEUR/{}/\n", msg));
+        players.broadcast_message(&ServerMessage::Winner(scores));
         drop(players);
 
         self.board.write().unwrap().reset();
     }
 
+    /// Renvoie la description de la grille courante, si elle provient du catalogue.
+    pub fn grid_description(&self) -> Option<String> {
+        self.board.read().unwrap().description().map(|d| d.to_string())
+    }
+
     /// Démarre un nouveau tour.
-    pub fn new_turn(&self) {
+    /// Renvoie les statistiques de la nouvelle grille et celles des grilles rejetées.
+    pub fn new_turn(&self) -> (GridStats, Vec<GridStats>) {
         let mut board = self.board.write().unwrap();
-        board.new_turn();
-        let grid = board.grid_str();
-        let msg = format!("TOUR/{}/\n", grid);
+        let rejected = board.new_turn(&**self.dict.read().unwrap());
+        let msg = board.turn_message();
+        let stats = board.stats();
         drop(board);
 
         let mut players = self.players.write().unwrap();
@@ -88,35 +109,138 @@ impl<T: Write + Clone> Game<T> {
 
         let mut running = self.turn_running.lock().unwrap();
         *running = true;
-        self.turn_cond.notify_all();
+        for mut stream in self.awaiting_welcome.lock().unwrap().drain(..) {
+            self.welcome(&mut stream);
+        }
+
+        (stats, rejected)
     }
 
     /// Met fin au tour courant.
     pub fn end_turn(&self) {
         *self.turn_running.lock().unwrap() = false;
-        let message = self.board.write().unwrap().turn_scores();
+        let board = self.board.read().unwrap();
+        let message = board.turn_scores_message();
+        let missed = board.missed_words_message();
+        let definitions = self.definitions.as_ref().and_then(|d| d.section_message(&board.scored_words()));
+        drop(board);
         let mut players = self.players.write().unwrap();
-        players.broadcast_message("RFIN/\n");
+        players.broadcast_message(&ServerMessage::EndTurn);
         players.broadcast_message(&message);
+        players.broadcast_message(&missed);
+        if let Some(definitions) = definitions {
+            players.broadcast_message(&definitions);
+        }
+    }
+
+    /// Renvoie la définition du mot `word`.
+    pub fn definition(&self, word: &str) -> Result<String, ServerError> {
+        self.definitions.as_ref()
+            .and_then(|d| d.get(word))
+            .map(|d| d.to_string())
+            .ok_or_else(|| ServerError::no_definition(word))
     }
 
     /// Analyse le mot `word`de trajectoire `trajectory` soumis par le joueur `username`.
     pub fn found(&self, username: &str, word: &str, trajectory: &str)
         -> Result<bool, ServerError>
     {
-        let word = word.to_lowercase();
-        self.check_exists(&word)?;
+        let normalized = self.dict.read().unwrap().normalizer().normalize(word)
+            .ok_or_else(|| ServerError::rejected_word(word, &Rejection::InvalidCharacters))?
+            .into_owned();
+        // La trajectoire est vérifiée avant le dictionnaire: sans quoi, un joueur pourrait
+        // savoir si n'importe quel mot existe, sans l'avoir trouvé sur la grille.
+        self.board.read().unwrap().check_trajectory(&normalized, trajectory)?;
+        if let Some(reason) = self.rejection(word) {
+            // Un mot absent du dictionnaire est conservé, afin que le joueur puisse contester
+            // ce refus pendant la pause.
+            if reason == Rejection::Unknown {
+                self.board.write().unwrap().reject_word(username, &normalized, trajectory);
+            }
+            return Err(ServerError::rejected_word(word, &reason))
+        }
         let mut board = self.board.write().unwrap();
-        board.submit_word(username, &word, trajectory)
+        board.submit_word(username, &normalized, trajectory)
     }
 
-    pub fn check_exists(&self, word: &str) -> Result<(), ServerError> {
-        let dict = self.dict.read().unwrap();
-        if dict.contains(word) {
-            Ok(())
+    /// Renvoie une erreur si un tour est en cours: la requête `request` n'est possible que pendant la pause.
+    fn check_pause(&self, request: &str) -> Result<(), ServerError> {
+        if *self.turn_running.lock().unwrap() {
+            Err(ServerError::turn_running(request))
         } else {
-            Err(ServerError::non_existing_word(word))
+            Ok(())
+        }
+    }
+
+    /// Ouvre la contestation du mot `word` par le joueur `username` et l'annonce à tous les joueurs.
+    pub fn challenge(&self, username: &str, word: &str) -> Result<(), ServerError> {
+        self.check_pause("CONTESTE")?;
+        let word = self.dict.read().unwrap().normalizer().normalize(word)
+            .ok_or_else(|| ServerError::cannot_challenge(username, word))?
+            .into_owned();
+        let msg = self.board.write().unwrap().challenge(username, &word)?;
+        self.players.write().unwrap().broadcast_message(&msg);
+        Ok(())
+    }
+
+    /// Enregistre le vote du joueur `username` sur la contestation du mot `word` par `challenger`.
+    /// Si le verdict est désormais certain, il est annoncé à tous les joueurs et renvoyé.
+    pub fn vote(&self, username: &str, challenger: &str, word: &str, approve: bool)
+        -> Result<Option<Verdict>, ServerError>
+    {
+        self.check_pause("VOTE")?;
+        let word = self.dict.read().unwrap().normalizer().normalize(word)
+            .ok_or_else(|| ServerError::no_challenge(challenger, word))?
+            .into_owned();
+        let mut board = self.board.write().unwrap();
+        let verdict = board.vote(username, challenger, &word, approve)?;
+        if let Some(verdict) = verdict {
+            let msg = board.verdict_message(challenger, &word, verdict);
+            drop(board);
+            self.players.write().unwrap().broadcast_message(&msg);
         }
+        Ok(verdict)
+    }
+
+    /// Clôt les contestations en attente, annonce leurs verdicts et les renvoie.
+    pub fn close_challenges(&self) -> Vec<(String, String, Verdict)> {
+        let mut board = self.board.write().unwrap();
+        let verdicts = board.close_challenges();
+        let messages = verdicts.iter()
+            .map(|(challenger, word, verdict)| board.verdict_message(challenger, word, *verdict))
+            .collect::<Vec<ServerMessage>>();
+        drop(board);
+        let mut players = self.players.write().unwrap();
+        messages.iter().for_each(|msg| players.broadcast_message(msg));
+        verdicts
+    }
+
+    /// Renvoie un indice au joueur `username`, en échange de quelques points.
+    pub fn hint(&self, username: &str) -> Result<Hint, ServerError> {
+        self.board.write().unwrap().hint(username)
+    }
+
+    /// Renvoie la raison pour laquelle le dictionnaire refuse le mot `word`, s'il le refuse.
+    /// Le verrou du dictionnaire est relâché avant le retour: l'appelant peut ensuite verrouiller
+    /// le plateau sans risquer d'interblocage avec `new_turn`, qui les prend dans l'ordre inverse.
+    fn rejection(&self, word: &str) -> Option<Rejection> {
+        self.dict.read().unwrap().rejection(word)
+    }
+
+    /// Recharge les fichiers dont dépend le dictionnaire, comme les listes de mots exclus.
+    /// Les solutions de la grille courante ne sont recalculées qu'au tour suivant.
+    pub fn reload_dict(&self) -> Result<(), DictError> {
+        self.dict.write().unwrap().reload()
+    }
+
+    /// Fait accepter le mot `word` par le dictionnaire, à la demande de `author`.
+    pub fn add_word(&self, word: &str, author: &str) -> Result<(), DictError> {
+        self.dict.write().unwrap().add_word(word, author)
+    }
+
+    /// Fait refuser le mot `word` par le dictionnaire, à la demande de `author`.
+    pub fn remove_word(&self, word: &str, author: &str) -> Result<(), DictError> {
+        self.dict.write().unwrap().remove_word(word, author)
     }
 
     pub fn is_connected(&self, username: &str) -> bool {
@@ -129,7 +253,7 @@ impl<T: Write + Clone> Game<T> {
     }
 
     pub fn chat_all(&self, msg: &str) -> Result<(), ServerError> {
-        let message = format!("RECEPTION/{}/\n", msg);
+        let message = ServerMessage::Chat(msg.to_string());
         self.players.write().unwrap().broadcast_message(&message);
         Ok(())
     }
@@ -141,8 +265,9 @@ pub mod test {
     use super::*;
     use super::super::{
         mock::StreamMock,
-        board::board::test::create_test_board,
-        dict::LocalDict,
+        protocol::Encoding,
+        board::{board::test::create_test_board, solver::solve, Classic},
+        dict::{LocalDict, Normalizer, TrieDict},
         players::test::create_test_players,
     };
 
@@ -155,6 +280,20 @@ pub mod test {
                    "BIENVENUE/LIDAREJULTNEATNG/1*user1*0/\n")
     }
 
+    #[test]
+    fn login_during_pause_is_welcomed_at_next_turn() {
+        let game: Game<StreamMock> = create_test_game();
+        game.end_turn();
+        let user1_stream = StreamMock::new();
+        game.login("user1", user1_stream.clone()).unwrap();
+        assert_eq!(user1_stream.to_string(), "");
+        game.new_turn();
+        let lines = user1_stream.to_string().lines().map(|l| l.to_string()).collect::<Vec<String>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("TOUR/"));
+        assert!(lines[1].starts_with("BIENVENUE/"));
+    }
+
     #[test]
     fn new_turn_is_broadcasted() {
         let mut game: Game<StreamMock> = create_test_game();
@@ -167,6 +306,41 @@ pub mod test {
         })
     }
 
+    #[test]
+    fn end_turn_is_broadcasted() {
+        let mut game: Game<StreamMock> = create_test_game();
+        let (players, streams) = create_test_players();
+        game.players = RwLock::new(players);
+        game.end_turn();
+        streams.iter().for_each(|s| {
+            let lines = s.to_string().lines().map(|l| l.to_owned()).collect::<Vec<String>>();
+            let n = lines.len();
+            assert_eq!(lines[n - 3], "RFIN/");
+            assert!(lines[n - 2].starts_with("BILANMOTS/"));
+            assert!(lines[n - 1].starts_with("MANQUES/"));
+        })
+    }
+
+    #[test]
+    fn definitions_of_scored_words() {
+        let definitions = Definitions::from_text("trident\tFourche à trois dents.\nile\tTerre.", Normalizer::new());
+        let mut game: Game<StreamMock> = create_test_game().with_definitions(definitions);
+        let (players, streams) = create_test_players();
+        game.players = RwLock::new(players);
+        game.board.write().unwrap().add_user("user1");
+        game.found("user1", "trident", "C2B1A2A3B2C3D2").unwrap();
+        game.end_turn();
+        streams.iter().for_each(|s| {
+            let last_line = s.to_string().lines().last().unwrap().to_owned();
+            assert_eq!(last_line, "DEFINITIONS/trident*Fourche à trois dents./")
+        });
+        assert_eq!(game.definition("Île").unwrap(), "Terre.");
+        match game.definition("dent") {
+            Err(ServerError::NoDefinition {..}) => (),
+            _ => panic!("dent has no definition !")
+        }
+    }
+
     #[test]
     fn end_session_is_broadcasted() {
         let mut game: Game<StreamMock> = create_test_game();
@@ -187,6 +361,34 @@ pub mod test {
         assert!(result.is_ok())
     }
 
+    #[test]
+    fn found_is_normalized() {
+        let game: Game<StreamMock> = create_test_game();
+        game.login("user1", StreamMock::new()).unwrap();
+        assert!(game.found("user1", "Île", "A2A1B2").is_ok());
+        match game.found("user1", "ile", "A2A1B2") {
+            Err(ServerError::AlreadyPlayed {..}) => (),
+            _ => panic!("{} has already been played !", "ile")
+        }
+    }
+
+    #[test]
+    fn solutions_are_accepted_whatever_the_case() {
+        let normalizer = Normalizer::new().with_accent_stripping(false).with_punctuation_removal(false);
+        let dict = TrieDict::from_text("TRIDENT\nIle\nlid\nParis", normalizer);
+        let grid = "LIDAREJULTNEATNG".chars().map(|c| c.to_string()).collect::<Vec<String>>();
+        let solutions = solve(&grid, 4, &dict, &Classic);
+        assert!(solutions.iter().any(|s| s.word == "trident"));
+
+        let game: Game<StreamMock> = Game::new(Players::new(), create_test_board(), dict);
+        *game.turn_running.lock().unwrap() = true;
+        game.login("user1", StreamMock::new()).unwrap();
+        for solution in solutions {
+            let trajectory = solution.trajectory.iter().map(|c| c.to_string()).collect::<String>();
+            assert!(game.found("user1", &solution.word.to_uppercase(), &trajectory).is_ok(), "{}", solution.word);
+        }
+    }
+
     #[test]
     fn found_already_played() {
         let game: Game<StreamMock> = create_test_game();
@@ -198,15 +400,56 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn hint_without_solutions() {
+        let game: Game<StreamMock> = create_test_game();
+        game.login("user1", StreamMock::new()).unwrap();
+        match game.hint("user1") {
+            Err(ServerError::NoHint {..}) => (),
+            _ => panic!("The grid has no solutions !")
+        }
+    }
+
     #[test]
     fn found_non_existing() {
         let game: Game<StreamMock> = create_test_game();
         match game.found("user1", "lid", "A1A2A3") {
-            Err(ServerError::NonExistingWord {..}) => (),
+            Err(ServerError::RejectedWord {..}) => (),
             _ => panic!("\"{}\" doesn't exist !", "lid")
         }
     }
 
+    #[test]
+    fn found_checks_trajectory_before_dictionary() {
+        let game: Game<StreamMock> = create_test_game();
+        for word in &["lid", "trident"] {
+            match game.found("user1", word, "A2A3A4") {
+                Err(ServerError::NoMatch {..}) => (),
+                r => panic!("The trajectory doesn't match \"{}\": {:?}", word, r)
+            }
+        }
+    }
+
+    #[test]
+    fn challenge_only_during_pause() {
+        let mut game: Game<StreamMock> = create_test_game();
+        let (players, streams) = create_test_players();
+        game.players = RwLock::new(players);
+        game.board.write().unwrap().add_user("user1");
+        assert!(game.found("user1", "Lid", "A1A2A3").is_err());
+        match game.challenge("user1", "lid") {
+            Err(ServerError::TurnRunning {..}) => (),
+            _ => panic!("Words can only be challenged during the pause !")
+        }
+        game.end_turn();
+        game.challenge("user1", "LID").unwrap();
+        streams.iter().for_each(|s| {
+            let last_line = s.to_string().lines().last().unwrap().to_owned();
+            assert_eq!(last_line, "CONTESTATION/user1/lid/AJOUT/")
+        });
+        assert_eq!(game.close_challenges(), vec![("user1".to_string(), "lid".to_string(), Verdict::Dismissed)]);
+    }
+
     #[test]
     fn chat_all() {
         let mut game: Game<StreamMock> = create_test_game();
@@ -219,7 +462,21 @@ pub mod test {
         })
     }
 
-    pub fn create_test_game<T: Write + Clone>() -> Game<T> {
+    #[test]
+    fn text_and_json_players_share_game() {
+        let game: Game<StreamMock> = create_test_game();
+        let text_stream = StreamMock::new();
+        let json_stream = StreamMock::new().with_encoding(Encoding::Json);
+        game.login("user1", text_stream.clone()).unwrap();
+        game.login("user2", json_stream.clone()).unwrap();
+        game.chat_all("test").unwrap();
+        assert_eq!(text_stream.to_string().lines().last().unwrap(), "RECEPTION/test/");
+        assert_eq!(json_stream.to_string().lines().last().unwrap(),
+                   "{\"message\":\"test\",\"type\":\"RECEPTION\"}");
+        assert!(json_stream.to_string().starts_with("{\"bonus\":null,\"grille\":\"LIDAREJULTNEATNG\""));
+    }
+
+    pub fn create_test_game<T: MessageSink + Clone>() -> Game<T> {
         let board = create_test_board();
         let players = Players::new();
         let dict = LocalDict::from_dictionary("dico_test.txt");
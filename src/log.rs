@@ -1,4 +1,5 @@
 use super::errors::ServerError;
+use super::board::{GridStats, Hint, Verdict};
 
 use std::{
     sync::mpsc::Receiver,
@@ -13,8 +14,20 @@ pub enum LogMsg {
     Accepted(String, String),
     MessageSent(String, String, String),
     GlobalMessage(String, String),
-    SessionStart,
+    SessionStart(u64),
     SessionEnd,
+    TurnStart(GridStats),
+    GridRejected(GridStats),
+    CatalogueGrid(String),
+    HintGiven(String, Hint),
+    Admin(String),
+    DictReloaded(String),
+    WordAdded(String, String),
+    WordRemoved(String, String),
+    Challenge(String, String),
+    Vote(String, String, String, bool),
+    Verdict(String, String, Verdict),
+    DefinitionGiven(String, String),
 }
 
 impl LogMsg {
@@ -38,6 +51,42 @@ impl LogMsg {
         LogMsg::MessageSent(sender.to_string(), receiver.to_string(), message.to_string())
     }
 
+    pub fn hint(username: &str, hint: &Hint) -> LogMsg {
+        LogMsg::HintGiven(username.to_string(), *hint)
+    }
+
+    pub fn admin(username: &str) -> LogMsg {
+        LogMsg::Admin(username.to_string())
+    }
+
+    pub fn dict_reloaded(username: &str) -> LogMsg {
+        LogMsg::DictReloaded(username.to_string())
+    }
+
+    pub fn word_added(username: &str, word: &str) -> LogMsg {
+        LogMsg::WordAdded(username.to_string(), word.to_string())
+    }
+
+    pub fn word_removed(username: &str, word: &str) -> LogMsg {
+        LogMsg::WordRemoved(username.to_string(), word.to_string())
+    }
+
+    pub fn challenge(username: &str, word: &str) -> LogMsg {
+        LogMsg::Challenge(username.to_string(), word.to_string())
+    }
+
+    pub fn vote(username: &str, challenger: &str, word: &str, approve: bool) -> LogMsg {
+        LogMsg::Vote(username.to_string(), challenger.to_string(), word.to_string(), approve)
+    }
+
+    pub fn verdict(challenger: &str, word: &str, verdict: Verdict) -> LogMsg {
+        LogMsg::Verdict(challenger.to_string(), word.to_string(), verdict)
+    }
+
+    pub fn definition(username: &str, word: &str) -> LogMsg {
+        LogMsg::DefinitionGiven(username.to_string(), word.to_string())
+    }
+
     pub fn global_message(sender: &str, message: &str)  -> LogMsg {
         LogMsg::GlobalMessage(sender.to_string(), message.to_string())
     }
@@ -45,18 +94,42 @@ impl LogMsg {
 
 impl fmt::Display for LogMsg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &LogMsg::Login(ref name) => write!(f, "{} vient de se connecter.", name),
-            &LogMsg::Logout(ref name) => write!(f, "{} vient de se déconnecter.", name),
-            &LogMsg::Accepted(ref name, ref word) => write!(f, "Le mot {} soumis par {} a été accepté.", word, name),
-            &LogMsg::Error(ref e) => write!(f, "Erreur: {}", e),
-            &LogMsg::MessageSent(ref s, ref r, ref m) =>
+        match *self {
+            LogMsg::Login(ref name) => write!(f, "{} vient de se connecter.", name),
+            LogMsg::Logout(ref name) => write!(f, "{} vient de se déconnecter.", name),
+            LogMsg::Accepted(ref name, ref word) => write!(f, "Le mot {} soumis par {} a été accepté.", word, name),
+            LogMsg::Error(ref e) => write!(f, "Erreur: {}", e),
+            LogMsg::MessageSent(ref s, ref r, ref m) =>
                 write!(f, "Le message <{}> soumis par {} a été envoyé à {}.", m, s , r),
-            &LogMsg::GlobalMessage(ref user, ref message) =>
+            LogMsg::GlobalMessage(ref user, ref message) =>
                 write!(f, "Le message <{}> soumis par {} a été envoyé à tous les utilisateurs.",
                        message, user),
-            &LogMsg::SessionStart => write!(f, "Début de la session."),
-            &LogMsg::SessionEnd => write!(f, "Fin de la session."),
+            LogMsg::SessionStart(seed) => write!(f, "Début de la session (graine {}).", seed),
+            LogMsg::SessionEnd => write!(f, "Fin de la session."),
+            LogMsg::TurnStart(ref stats) =>
+                write!(f, "Début du tour (graine {}): la grille contient {} mots pour un total de {} points, \
+                           le plus long faisant {} lettres.",
+                       stats.seed, stats.nb_words, stats.score, stats.longest),
+            LogMsg::GridRejected(ref stats) =>
+                write!(f, "Grille rejetée (graine {}): {} mots pour un total de {} points, \
+                           le plus long faisant {} lettres.",
+                       stats.seed, stats.nb_words, stats.score, stats.longest),
+            LogMsg::CatalogueGrid(ref description) =>
+                write!(f, "Grille du catalogue: {}.", description),
+            LogMsg::HintGiven(ref name, ref hint) => write!(f, "Indice donné à {}: {}.", name, hint),
+            LogMsg::Admin(ref name) => write!(f, "{} est désormais administrateur.", name),
+            LogMsg::DictReloaded(ref name) => write!(f, "Listes de mots du dictionnaire rechargées par {}.", name),
+            LogMsg::WordAdded(ref name, ref word) => write!(f, "Le mot {} a été ajouté au dictionnaire par {}.", word, name),
+            LogMsg::WordRemoved(ref name, ref word) => write!(f, "Le mot {} a été retiré du dictionnaire par {}.", word, name),
+            LogMsg::Challenge(ref name, ref word) => write!(f, "{} conteste le mot {}.", name, word),
+            LogMsg::Vote(ref name, ref challenger, ref word, approve) =>
+                write!(f, "{} a voté {} la contestation du mot {} par {}.",
+                       name, if approve { "pour" } else { "contre" }, word, challenger),
+            LogMsg::DefinitionGiven(ref name, ref word) => write!(f, "Définition du mot {} envoyée à {}.", word, name),
+            LogMsg::Verdict(ref challenger, ref word, verdict) => match verdict {
+                Verdict::Upheld => write!(f, "La contestation du mot {} par {} est acceptée.", word, challenger),
+                Verdict::Dismissed => write!(f, "La contestation du mot {} par {} est rejetée.", word, challenger),
+            },
         }
     }
 }
@@ -1,37 +1,56 @@
 #[macro_use] extern crate failure;
-#[macro_use] extern crate lazy_static;
 extern crate rand;
 extern crate unidecode;
 extern crate clap;
-extern crate regex;
+extern crate tokio;
+extern crate memmap2;
+extern crate serde_json;
 
 mod board;
-mod cloneable_stream;
+mod client_writer;
 mod game;
 mod players;
 mod log;
 mod mock;
 mod errors;
 mod server;
+mod protocol;
 mod dict;
 
-use board::Board;
-use dict::LocalDict;
+use board::{
+    Board, Catalogue, Classic, Dices, GridFilter, Order, ScoreTable, Scrabble, ScoringRule, GRID_SIZES,
+};
+use dict::{Definitions, Dict, HttpDict, Normalizer, OverlayDict};
 use players::Players;
 
 use std::{
     sync::{mpsc::channel, Arc},
-    net::TcpListener,
+    path::Path,
+    process,
     thread,
     time::Duration,
 };
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use tokio::{net::TcpListener, runtime::Runtime};
 
 fn main() {
     run();
 }
 
+/// Renvoie la normalisation des mots du dictionnaire choisie par l'option `normalisation`.
+fn normalizer_of_conf(conf: &ArgMatches) -> Normalizer {
+    match conf.values_of("normalisation") {
+        Some(steps) => {
+            let steps = steps.collect::<Vec<&str>>();
+            Normalizer::new()
+                .with_accent_stripping(steps.contains(&"accents"))
+                .with_punctuation_removal(steps.contains(&"ponctuation"))
+        },
+        None => Normalizer::new(),
+    }
+}
+
 fn run() {
     let conf = App::new("boggle_server")
         .author("Geoffrey Copin - 3201050")
@@ -46,11 +65,116 @@ fn run() {
         .arg(Arg::with_name("immediat")
             .long("immediat")
             .help("Active la verification immédiate"))
-        .arg(Arg::with_name("grilles")
-            .long("grilles")
-            .value_name("grille1 grille2")
-            .takes_value(true)
-            .multiple(true))
+        .arg(Arg::with_name("contestation_adverse")
+            .long("contestation_adverse")
+            .help("Permet de contester les mots acceptés des adversaires, en plus de ses propres mots refusés."))
+        .arg(Arg::with_name("catalogue")
+            .long("catalogue")
+            .value_name("FICHIER")
+            .help("Catalogue des grilles à utiliser à la place des grilles aléatoires.")
+            .takes_value(true))
+        .arg(Arg::with_name("ordre")
+            .long("ordre")
+            .value_name("ORDRE")
+            .help("Ordre dans lequel les grilles du catalogue sont jouées.")
+            .possible_values(&["sequentiel", "aleatoire"])
+            .takes_value(true))
+        .arg(Arg::with_name("dico")
+            .long("dico")
+            .value_name("FICHIER")
+            .help("Dictionnaire à utiliser: liste de mots ou dictionnaire compilé (dico_fr.bin par défaut).")
+            .takes_value(true))
+        .arg(Arg::with_name("definitions")
+            .long("definitions")
+            .value_name("FICHIER")
+            .help("Définitions des mots, une par ligne après le mot et une tabulation.")
+            .takes_value(true))
+        .arg(Arg::with_name("dico_http")
+            .long("dico_http")
+            .value_name("URL")
+            .help("Service HTTP de dictionnaire partagé vérifiant les mots soumis, le dictionnaire local servant de secours et à résoudre les grilles.")
+            .takes_value(true))
+        .arg(Arg::with_name("delai_http")
+            .long("delai_http")
+            .value_name("NB_MILLISECS")
+            .help("Délai au-delà duquel le dictionnaire local est consulté à la place du service HTTP.")
+            .takes_value(true))
+        .arg(Arg::with_name("cache_http")
+            .long("cache_http")
+            .value_name("NB_MOTS")
+            .help("Nombre de réponses du service HTTP conservées en cache.")
+            .takes_value(true))
+        .arg(Arg::with_name("normalisation")
+            .long("normalisation")
+            .value_name("ETAPES")
+            .help("Étapes de normalisation des mots du dictionnaire, toutes par défaut. \
+                   Les mots sont toujours passés en minuscules.")
+            .possible_values(&["accents", "ponctuation", "aucune"])
+            .use_delimiter(true)
+            .multiple(true)
+            .global(true)
+            .takes_value(true))
+        .arg(Arg::with_name("mots_autorises")
+            .long("mots_autorises")
+            .value_name("FICHIER")
+            .help("Liste de mots acceptés en plus de ceux du dictionnaire, où sont enregistrés les mots ajoutés par un administrateur.")
+            .takes_value(true))
+        .arg(Arg::with_name("mots_exclus")
+            .long("mots_exclus")
+            .value_name("FICHIER")
+            .help("Liste de mots refusés même s'ils figurent dans le dictionnaire, où sont enregistrés les mots retirés par un administrateur.")
+            .takes_value(true))
+        .arg(Arg::with_name("admin_mdp")
+            .long("admin_mdp")
+            .value_name("MOT_DE_PASSE")
+            .help("Mot de passe permettant à un joueur de devenir administrateur. Nécessite --mots_autorises et --mots_exclus.")
+            .takes_value(true))
+        .arg(Arg::with_name("graine")
+            .long("graine")
+            .value_name("GRAINE")
+            .help("Graine des grilles aléatoires: chaque session produit la même suite de grilles.")
+            .takes_value(true))
+        .arg(Arg::with_name("taille")
+            .long("taille")
+            .value_name("NB_CASES")
+            .help("Définit le nombre de lignes et de colonnes de la grille.")
+            .possible_values(&["4", "5", "6"])
+            .takes_value(true))
+        .arg(Arg::with_name("des")
+            .long("des")
+            .value_name("LANGUE|FICHIER")
+            .help("Jeu de dés à utiliser: fr, en ou le chemin d'un fichier de dés.")
+            .takes_value(true))
+        .arg(Arg::with_name("score")
+            .long("score")
+            .value_name("BAREME|FICHIER")
+            .help("Barème des points: classique, scrabble ou le chemin d'un fichier de barème.")
+            .takes_value(true))
+        .arg(Arg::with_name("bonus")
+            .long("bonus")
+            .value_name("NB_CASES")
+            .help("Nombre de cases bonus placées au hasard sur les grilles sans bonus fixés par le catalogue.")
+            .takes_value(true))
+        .arg(Arg::with_name("penalite_indice")
+            .long("penalite_indice")
+            .value_name("NB_POINTS")
+            .help("Nombre de points retirés à un joueur pour chaque indice demandé.")
+            .takes_value(true))
+        .arg(Arg::with_name("mots_min")
+            .long("mots_min")
+            .value_name("NB_MOTS")
+            .help("Nombre minimum de mots que doit contenir une grille aléatoire.")
+            .takes_value(true))
+        .arg(Arg::with_name("score_min")
+            .long("score_min")
+            .value_name("NB_POINTS")
+            .help("Total minimum des points des mots d'une grille aléatoire.")
+            .takes_value(true))
+        .arg(Arg::with_name("long_mot_min")
+            .long("long_mot_min")
+            .value_name("NB_LETTRES")
+            .help("Une grille aléatoire doit contenir au moins un mot de NB_LETTRES lettres.")
+            .takes_value(true))
         .arg(Arg::with_name("duree_tour")
             .long("duree_tour")
             .value_name("NB_SECS")
@@ -61,49 +185,178 @@ fn run() {
             .value_name("NB_SECS")
             .help("Définit la durée de la pause entre deux tours.")
             .takes_value(true))
+        .subcommand(SubCommand::with_name("compiler")
+            .about("Compile une liste de mots en un dictionnaire chargé beaucoup plus rapidement.")
+            .arg(Arg::with_name("source")
+                .value_name("LISTE")
+                .required(true))
+            .arg(Arg::with_name("destination")
+                .value_name("DICO_COMPILE")
+                .required(true)))
         .get_matches();
 
+    if let Some(conf) = conf.subcommand_matches("compiler") {
+        let source = conf.value_of("source").unwrap();
+        let destination = conf.value_of("destination").unwrap();
+        match dict::CompiledDict::compile_file(source, destination, normalizer_of_conf(conf)) {
+            Ok(size) => println!("Dictionnaire compilé dans {} ({} octets).", destination, size),
+            Err(e) => {
+                eprintln!("Compilation impossible: {}", e);
+                process::exit(1)
+            }
+        }
+        return
+    }
+
     let port = conf.value_of("port").unwrap_or("2018");
     let nb_tours = conf.value_of("tours").unwrap_or("10").parse::<u64>()
         .expect("tours doit être un nombre entier!");
     let immediat = conf.is_present("immediat");
-    let grilles = conf.values_of("grilles").unwrap_or_default()
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
+    let contestation_adverse = conf.is_present("contestation_adverse");
+    let graine = conf.value_of("graine")
+        .map(|g| g.parse::<u64>().expect("graine doit être un nombre entier positif!"));
+    let taille = conf.value_of("taille").unwrap_or("4").parse::<usize>()
+        .ok()
+        .filter(|t| GRID_SIZES.contains(t))
+        .expect("taille doit valoir 4, 5 ou 6!");
+    let des = conf.value_of("des").unwrap_or("fr");
+    let des = if Path::new(des).is_file() {
+        Dices::from_file(des, taille)
+    } else {
+        Dices::of_language(des, taille)
+    }.unwrap_or_else(|e| {
+        eprintln!("Jeu de dés invalide: {}", e);
+        process::exit(1)
+    });
+    let bareme: Box<dyn ScoringRule> = match conf.value_of("score").unwrap_or("classique") {
+        "classique" => Box::new(Classic),
+        "scrabble" => Box::new(Scrabble),
+        fichier => Box::new(ScoreTable::from_file(fichier).unwrap_or_else(|e| {
+            eprintln!("Barème invalide: {}", e);
+            process::exit(1)
+        })),
+    };
+    let bonus = conf.value_of("bonus").unwrap_or("0").parse::<usize>()
+        .ok()
+        .filter(|&b| b <= taille * taille)
+        .expect("bonus doit être un nombre entier inférieur au nombre de cases!");
+    let penalite_indice = conf.value_of("penalite_indice").unwrap_or("1").parse::<u32>()
+        .expect("penalite_indice doit être un nombre entier!");
+    let ordre = match conf.value_of("ordre") {
+        Some("aleatoire") => Order::Shuffled,
+        _ => Order::Sequential,
+    };
+    let catalogue = conf.value_of("catalogue")
+        .map(|f| Catalogue::from_file(f, taille, &des))
+        .unwrap_or_else(|| Ok(Catalogue::new()))
+        .unwrap_or_else(|e| {
+            eprintln!("Catalogue invalide: {}", e);
+            process::exit(1)
+        })
+        .with_order(ordre);
+    let filtre = GridFilter {
+        min_words: conf.value_of("mots_min").unwrap_or("0").parse::<usize>()
+            .expect("mots_min doit être un nombre entier!"),
+        min_score: conf.value_of("score_min").unwrap_or("0").parse::<u32>()
+            .expect("score_min doit être un nombre entier!"),
+        min_longest: conf.value_of("long_mot_min").unwrap_or("0").parse::<usize>()
+            .expect("long_mot_min doit être un nombre entier!"),
+        ..GridFilter::default()
+    };
     let duree_tour = conf.value_of("duree_tour").unwrap_or("180").parse::<u64>()
         .map_err(|e| eprintln!("{}", e))
-        .map(|d| Duration::from_secs(d))
+        .map(Duration::from_secs)
         .unwrap();
     let duree_pause = conf.value_of("duree_pause").unwrap_or("10").parse::<u64>()
         .map_err(|e| eprintln!("{}", e))
-        .map(|d| Duration::from_secs(d))
+        .map(Duration::from_secs)
         .unwrap();
 
-    let board = Board::new(immediat, grilles);
-    let dict = LocalDict::new();
+    let dico = conf.value_of("dico").unwrap_or("dico_fr.bin");
+    let mut dict = dict::load(dico, normalizer_of_conf(&conf)).unwrap_or_else(|e| {
+        eprintln!("Dictionnaire invalide: {}", e);
+        process::exit(1)
+    });
+    if conf.is_present("admin_mdp") && ! (conf.is_present("mots_autorises") && conf.is_present("mots_exclus")) {
+        eprintln!("L'administration du dictionnaire nécessite les options --mots_autorises et --mots_exclus.");
+        process::exit(1)
+    }
+    if let Some(url) = conf.value_of("dico_http") {
+        let mut http = HttpDict::new(url).unwrap_or_else(|e| {
+            eprintln!("Dictionnaire invalide: {}", e);
+            process::exit(1)
+        });
+        if let Some(delai) = conf.value_of("delai_http") {
+            let delai = delai.parse::<u64>().expect("delai_http doit être un nombre entier!");
+            http = http.with_timeout(Duration::from_millis(delai));
+        }
+        if let Some(taille) = conf.value_of("cache_http") {
+            http = http.with_cache_size(taille.parse::<usize>().expect("cache_http doit être un nombre entier!"));
+        }
+        dict = Box::new(http.with_normalizer(normalizer_of_conf(&conf)).with_fallback(dict));
+    }
+    if conf.is_present("mots_autorises") || conf.is_present("mots_exclus") {
+        let mut overlay = OverlayDict::new(dict);
+        if let Some(fichier) = conf.value_of("mots_autorises") {
+            overlay = overlay.with_allow_list(fichier);
+        }
+        if let Some(fichier) = conf.value_of("mots_exclus") {
+            overlay = overlay.with_deny_list(fichier);
+        }
+        if let Err(e) = overlay.reload() {
+            eprintln!("Liste de mots invalide: {}", e);
+            process::exit(1)
+        }
+        let (autorises, exclus) = overlay.list_sizes();
+        println!("Listes de mots chargées: {} mots autorisés, {} mots exclus.", autorises, exclus);
+        dict = Box::new(overlay);
+    }
+    if let Err(e) = catalogue.check_solutions(taille, &dict) {
+        eprintln!("Catalogue invalide: {}", e);
+        process::exit(1)
+    }
+
+    let mut board = Board::new(immediat)
+        .with_size(taille)
+        .with_dices(des)
+        .with_filter(filtre)
+        .with_scoring(bareme)
+        .with_bonus(bonus)
+        .with_hint_penalty(penalite_indice)
+        .with_opponent_challenges(contestation_adverse)
+        .with_catalogue(catalogue);
+    if let Some(graine) = graine {
+        board = board.with_seed(graine);
+    }
     let players = Players::new();
-    let game = game::Game::new(players, board, dict);
+    let mut game = game::Game::new(players, board, dict);
+    if let Some(fichier) = conf.value_of("definitions") {
+        let definitions = Definitions::from_file(fichier, normalizer_of_conf(&conf)).unwrap_or_else(|e| {
+            eprintln!("Définitions invalides: {}", e);
+            process::exit(1)
+        });
+        game = game.with_definitions(definitions);
+    }
 
     let (log_send, log_receive) = channel();
-    let (server_send, server_receive) = channel();
 
-    let server = server::Server::new(log_send.clone())
-        .with_game(game)
+    let mut server = server::Server::new(game, log_send.clone())
         .with_pause_duration(duree_pause)
         .with_turn_duration(duree_tour)
         .with_nb_turn(nb_tours);
-
-    thread::spawn(|| server::run(Arc::new(server), server_receive));
+    if let Some(mdp) = conf.value_of("admin_mdp") {
+        server = server.with_admin_password(mdp);
+    }
 
     thread::spawn(|| log::log(log_receive));
 
-    let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
-        .expect("Cannot start listener!");
+    let runtime = Runtime::new().expect("Cannot start runtime!");
+    runtime.block_on(async move {
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await
+            .expect("Cannot start listener!");
 
-    println!("Démarrage sur le port {}...", port);
+        println!("Démarrage sur le port {}...", port);
 
-    for stream in listener.incoming() {
-        let s = stream.unwrap();
-        server_send.send(s).expect("Le server s'est arrété!");
-    }
+        server::run(Arc::new(server), listener).await
+    });
 }
@@ -1,23 +1,34 @@
 #![cfg(test)]
 
+use super::protocol::{Encoding, MessageSink, ServerMessage};
+
 use std::{
     cell::RefCell,
+    fmt,
     io::{Write, Error},
     rc::Rc,
 };
 
 #[derive(Debug, Clone)]
 pub struct StreamMock {
-    data: Rc<RefCell<Vec<u8>>>
+    data: Rc<RefCell<Vec<u8>>>,
+    encoding: Encoding,
 }
 
 impl StreamMock {
     pub fn new() -> StreamMock {
-        StreamMock { data: Rc::new(RefCell::new(Vec::new())) }
+        StreamMock { data: Rc::new(RefCell::new(Vec::new())), encoding: Encoding::Text }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> StreamMock {
+        self.encoding = encoding;
+        self
     }
+}
 
-    pub fn to_string(&self) -> String {
-        String::from_utf8(self.data.borrow().to_vec()).unwrap()
+impl fmt::Display for StreamMock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8(self.data.borrow().to_vec()).unwrap())
     }
 }
 
@@ -29,4 +40,10 @@ impl Write for StreamMock {
     fn flush(&mut self) -> Result<(), Error> {
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl MessageSink for StreamMock {
+    fn send(&mut self, message: &ServerMessage) -> Result<(), Error> {
+        self.write_all(self.encoding.encode(message).as_bytes())
+    }
+}
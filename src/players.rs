@@ -1,15 +1,15 @@
 use super::errors::ServerError;
+use super::protocol::{MessageSink, ServerMessage};
 
 use std::{
-    io::Write,
     collections::HashMap,
 };
 
-pub struct Players<T: Write> {
+pub struct Players<T: MessageSink> {
     players: HashMap<String, T>
 }
 
-impl<T: Write> Players<T> {
+impl<T: MessageSink> Players<T> {
     pub fn new() -> Self {
         Players { players: HashMap::new()}
     }
@@ -31,15 +31,15 @@ impl<T: Write> Players<T> {
     }
 
     fn register_user(&mut self, pseudo: &str, stream: T) {
-        let message = format!("CONNECTE/{}/\n", pseudo);
+        let message = ServerMessage::Connected(pseudo.to_string());
         self.broadcast_message(&message);
         self.players.insert(pseudo.to_string(), stream);
     }
 
     /// Envoie le message `message` à tous les joueurs connectés.
-    pub fn broadcast_message(&mut self, message: &str) {
+    pub fn broadcast_message(&mut self, message: &ServerMessage) {
         for s in self.players.values_mut() {
-            if let Err(e) = s.write(message.as_bytes()) {
+            if let Err(e) = s.send(message) {
                 eprintln!("Error while broadcastin message: {}", e)
             }
         }
@@ -56,7 +56,10 @@ impl<T: Write> Players<T> {
                                                  ServerError::non_existing_user(recv)))
         }
         let stream = self.players.get_mut(recv).unwrap();
-        stream.write(format!("PRECEPTION/{}/{}/\n", msg, send).as_bytes()).unwrap();
+        let message = ServerMessage::PrivateChat { message: msg.to_string(), sender: send.to_string() };
+        if let Err(e) = stream.send(&message) {
+            eprintln!("Error while sending message: {}", e)
+        }
         Ok(())
     }
 
@@ -73,7 +76,7 @@ impl<T: Write> Players<T> {
 
     fn remove_user(&mut self, username: &str) {
         self.players.remove(username);
-        self.broadcast_message(&format!("DECONNEXION/{}/", username));
+        self.broadcast_message(&ServerMessage::Disconnected(username.to_string()));
     }
 }
 
@@ -118,7 +121,7 @@ pub mod test {
         players.logout("user2").unwrap();
         let users = players.players;
 
-        let actual = users.keys().map(|s| s.clone()).collect::<HashSet<String>>();
+        let actual = users.keys().cloned().collect::<HashSet<String>>();
 
         let mut expected = HashSet::new();
         expected.insert("user3".to_string());
@@ -143,7 +146,8 @@ pub mod test {
         let users = players.players;
         users.values().for_each(|s| {
             let last_line = s.to_string().lines().last().unwrap().to_owned();
-            assert_eq!(last_line, "DECONNEXION/user2/")
+            assert_eq!(last_line, "DECONNEXION/user2/");
+            assert!(s.to_string().ends_with("DECONNEXION/user2/\n"))
         })
     }
 
@@ -176,11 +180,11 @@ pub mod test {
 
     pub fn create_test_players() -> (Players<StreamMock>, Vec<StreamMock>) {
         let mut players = create_empty_players();
-        let streams = add_users(&mut players, &vec!["user1", "user2", "user3"]);
+        let streams = add_users(&mut players, &["user1", "user2", "user3"]);
         (players, streams)
     }
 
-    fn create_empty_players<T: Write>() -> Players<T> {
+    fn create_empty_players<T: MessageSink>() -> Players<T> {
         Players::new()
     }
 
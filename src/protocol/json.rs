@@ -0,0 +1,117 @@
+use super::{Scores, ServerMessage};
+use super::super::board::Hint;
+
+use serde_json::{json, Value};
+
+/// Code le message `message` en un objet JSON sur une ligne, retour à la ligne final compris.
+/// Le champ `type` porte le nom du message dans le format texte.
+pub fn encode(message: &ServerMessage) -> String {
+    let value = match *message {
+        ServerMessage::Session => json!({ "type": "SESSION" }),
+        ServerMessage::Welcome { ref grid, turn, ref scores, ref bonus } => json!({
+            "type": "BIENVENUE", "grille": grid, "tour": turn, "scores": scores_value(scores), "bonus": bonus,
+        }),
+        ServerMessage::Connected(ref user) => json!({ "type": "CONNECTE", "pseudo": user }),
+        ServerMessage::Disconnected(ref user) => json!({ "type": "DECONNEXION", "pseudo": user }),
+        ServerMessage::Turn { ref grid, ref bonus } => json!({ "type": "TOUR", "grille": grid, "bonus": bonus }),
+        ServerMessage::EndTurn => json!({ "type": "RFIN" }),
+        ServerMessage::TurnWords { ref words, ref scores } => {
+            let words = words.iter()
+                .map(|(user, words)| json!({ "pseudo": user, "mots": words }))
+                .collect::<Vec<Value>>();
+            json!({ "type": "BILANMOTS", "mots": words, "scores": scores_value(scores) })
+        },
+        ServerMessage::MissedWords { ref words, percentage } =>
+            json!({ "type": "MANQUES", "mots": words, "pourcentage": percentage }),
+        ServerMessage::Definitions(ref definitions) => {
+            let definitions = definitions.iter()
+                .map(|(word, definition)| json!({ "mot": word, "definition": definition }))
+                .collect::<Vec<Value>>();
+            json!({ "type": "DEFINITIONS", "definitions": definitions })
+        },
+        ServerMessage::Winner(ref scores) => json!({ "type": "VAINQUEUR", "scores": scores_value(scores) }),
+        ServerMessage::ValidWord(ref word) => json!({ "type": "MVALIDE", "mot": word }),
+        ServerMessage::InvalidWord(ref reason) => json!({ "type": "MINVALIDE", "raison": reason }),
+        ServerMessage::Hint(hint) => match hint {
+            Hint::FirstLetter(letter) => json!({ "type": "INDICE", "indice": "LETTRE", "lettre": letter }),
+            Hint::Length(length) => json!({ "type": "INDICE", "indice": "LONGUEUR", "longueur": length }),
+            Hint::StartingCell(cell) => json!({ "type": "INDICE", "indice": "CASE", "case": cell.to_string() }),
+        },
+        ServerMessage::Chat(ref msg) => json!({ "type": "RECEPTION", "message": msg }),
+        ServerMessage::PrivateChat { message: ref msg, ref sender } =>
+            json!({ "type": "PRECEPTION", "message": msg, "expediteur": sender }),
+        ServerMessage::Admin => json!({ "type": "ADMIN" }),
+        ServerMessage::Reloaded => json!({ "type": "RECHARGE" }),
+        ServerMessage::WordAdded(ref word) => json!({ "type": "AJOUTE", "mot": word }),
+        ServerMessage::WordRemoved(ref word) => json!({ "type": "RETIRE", "mot": word }),
+        ServerMessage::Definition { ref word, ref definition } =>
+            json!({ "type": "DEFINITION", "mot": word, "definition": definition }),
+        ServerMessage::Challenge { ref challenger, ref word, ref kind } => json!({
+            "type": "CONTESTATION", "contestataire": challenger, "mot": word, "objet": kind.to_string(),
+        }),
+        ServerMessage::Verdict { ref challenger, ref word, verdict, ref scores } => json!({
+            "type": "VERDICT", "contestataire": challenger, "mot": word, "verdict": verdict.to_string(),
+            "scores": scores_value(scores),
+        }),
+        ServerMessage::Error { ref code, message: ref msg } => json!({ "type": "ERREUR", "code": code, "message": msg }),
+    };
+    format!("{}\n", value)
+}
+
+/// Renvoie la liste des scores `scores`, un objet par joueur.
+fn scores_value(scores: &Scores) -> Value {
+    Value::Array(scores.iter().map(|(user, score)| json!({ "pseudo": user, "score": score })).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::super::{board::{Cell, ChallengeKind, Verdict}, errors::ServerError};
+
+    fn decode(message: &ServerMessage) -> Value {
+        let encoded = encode(message);
+        assert_eq!(encoded.matches('\n').count(), 1, "{}", encoded);
+        serde_json::from_str(&encoded).unwrap()
+    }
+
+    #[test]
+    fn encode_messages() {
+        let scores = vec![("alice".to_string(), 3), ("b*b".to_string(), 0)];
+        let welcome = ServerMessage::Welcome {
+            grid: "LIDA".to_string(), turn: 2, scores: scores.clone(), bonus: None,
+        };
+        assert_eq!(decode(&welcome), json!({
+            "type": "BIENVENUE", "grille": "LIDA", "tour": 2, "bonus": null,
+            "scores": [{ "pseudo": "alice", "score": 3 }, { "pseudo": "b*b", "score": 0 }],
+        }));
+        let words = ServerMessage::TurnWords {
+            words: vec![("alice".to_string(), vec!["lid".to_string()]), ("b*b".to_string(), vec![])],
+            scores: scores.clone(),
+        };
+        assert_eq!(decode(&words)["mots"], json!([{ "pseudo": "alice", "mots": ["lid"] },
+                                                  { "pseudo": "b*b", "mots": [] }]));
+        assert_eq!(decode(&ServerMessage::Hint(Hint::StartingCell(Cell::new(2, 1)))),
+                   json!({ "type": "INDICE", "indice": "CASE", "case": "C2" }));
+        let challenge = ServerMessage::Challenge {
+            challenger: "bob".to_string(), word: "lid".to_string(), kind: ChallengeKind::Rejected,
+        };
+        assert_eq!(decode(&challenge)["objet"], "AJOUT");
+        let verdict = ServerMessage::Verdict {
+            challenger: "bob".to_string(), word: "lid".to_string(), verdict: Verdict::Upheld, scores,
+        };
+        assert_eq!(decode(&verdict)["contestataire"], "bob");
+        assert_eq!(decode(&verdict)["verdict"], "ACCEPTEE");
+        assert_eq!(decode(&ServerMessage::error(&ServerError::no_hint("bob")))["code"], "AUCUN_INDICE");
+        assert_eq!(encode(&ServerMessage::EndTurn), "{\"type\":\"RFIN\"}\n");
+    }
+
+    #[test]
+    fn hostile_text_stays_on_one_line() {
+        for text in &["a/b*c", "fin\\", "a\nb", "\"}{", "é"] {
+            let chat = ServerMessage::PrivateChat { message: text.to_string(), sender: text.to_string() };
+            let value = decode(&chat);
+            assert_eq!(value["message"], *text);
+            assert_eq!(value["expediteur"], *text);
+        }
+    }
+}
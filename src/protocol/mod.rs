@@ -0,0 +1,372 @@
+mod json;
+
+use super::board::{ChallengeKind, Hint, Verdict};
+use super::errors::ServerError;
+
+use std::{
+    borrow::Cow,
+    io,
+};
+
+/// Séparateur des champs d'un message.
+pub const FIELD_SEP: char = '/';
+/// Séparateur des éléments d'une liste.
+pub const LIST_SEP: char = '*';
+/// Caractère d'échappement.
+const ESCAPE: char = '\\';
+
+/// Échappe les caractères spéciaux de `text`.
+///
+/// Un message est une suite de champs terminés par `/`, sur une seule ligne. Un champ peut
+/// contenir une liste d'éléments séparés par `*`. Dans un champ ou un élément, les caractères
+/// `/`, `*` et `\` sont précédés d'un `\`, et un retour à la ligne est écrit `\n`: un pseudo ou
+/// un message de chat peut ainsi contenir n'importe quel caractère sans corrompre le message.
+fn escape(text: &str) -> Cow<'_, str> {
+    if ! text.contains([FIELD_SEP, LIST_SEP, ESCAPE, '\n']) {
+        return Cow::Borrowed(text)
+    }
+    let mut escaped = String::with_capacity(text.len() + 2);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            FIELD_SEP | LIST_SEP | ESCAPE => { escaped.push(ESCAPE); escaped.push(c) },
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Décode le texte échappé `text`.
+/// Un `\` final, qui n'échappe aucun caractère, est conservé tel quel.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if ! text.contains(ESCAPE) {
+        return Cow::Borrowed(text)
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != ESCAPE {
+            unescaped.push(c);
+            continue
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push(ESCAPE),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Découpe `text` selon les séparateurs `sep` qui ne sont pas échappés.
+/// Les morceaux restent échappés.
+fn split(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut escaped) = (0, false);
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == ESCAPE {
+            escaped = true;
+        } else if c == sep {
+            parts.push(&text[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Construit le champ contenant la liste des éléments `items`, chacun échappé.
+fn list<I: IntoIterator<Item = S>, S: AsRef<str>>(items: I) -> String {
+    items.into_iter()
+        .map(|item| escape(item.as_ref()).into_owned())
+        .collect::<Vec<String>>()
+        .join(&LIST_SEP.to_string())
+}
+
+/// Décode les éléments de la liste `field`, telle que reçue dans un message.
+/// Un champ vide est une liste vide. Les requêtes des clients ne contenant pas de liste,
+/// seuls les tests l'utilisent.
+#[cfg(test)]
+pub fn decode_list(field: &str) -> Vec<String> {
+    if field.is_empty() {
+        return Vec::new()
+    }
+    split(field, LIST_SEP).into_iter().map(|item| unescape(item).into_owned()).collect()
+}
+
+/// Construit le message `kind` dont les champs `fields` sont déjà codés, avec `escape` ou `list`.
+fn message<S: AsRef<str>>(kind: &str, fields: &[S]) -> String {
+    let mut message = format!("{}{}", kind, FIELD_SEP);
+    for field in fields {
+        message.push_str(field.as_ref());
+        message.push(FIELD_SEP);
+    }
+    message.push('\n');
+    message
+}
+
+/// Construit le message `kind`, sans champ.
+fn empty_message(kind: &str) -> String {
+    message::<&str>(kind, &[])
+}
+
+/// Découpe la ligne `line` en champs, sans les décoder: un champ contenant une liste
+/// se décode avec `decode_list`, les autres avec `unescape`.
+pub fn decode_message(line: &str) -> Vec<&str> {
+    split(line, FIELD_SEP)
+}
+
+/// Scores des joueurs, dans l'ordre où ils sont envoyés.
+pub type Scores = Vec<(String, u32)>;
+
+/// Message envoyé par le serveur à un client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// Début d'une session.
+    Session,
+    /// Accueil d'un joueur arrivant en cours de session.
+    Welcome { grid: String, turn: u64, scores: Scores, bonus: Option<String> },
+    /// Connexion d'un joueur.
+    Connected(String),
+    /// Déconnexion d'un joueur.
+    Disconnected(String),
+    /// Début d'un tour.
+    Turn { grid: String, bonus: Option<String> },
+    /// Fin d'un tour.
+    EndTurn,
+    /// Bilan du tour: mots trouvés par chaque joueur, puis scores.
+    TurnWords { words: Vec<(String, Vec<String>)>, scores: Scores },
+    /// Meilleurs mots manqués et pourcentage des points de la grille remportés.
+    MissedWords { words: Vec<String>, percentage: u32 },
+    /// Définitions des meilleurs mots du tour.
+    Definitions(Vec<(String, String)>),
+    /// Fin de la session, avec les scores finaux.
+    Winner(Scores),
+    /// Mot accepté par la vérification immédiate.
+    ValidWord(String),
+    /// Mot refusé par la vérification immédiate, avec la raison du refus.
+    InvalidWord(String),
+    /// Indice demandé par le joueur.
+    Hint(Hint),
+    /// Message de chat envoyé à tous les joueurs.
+    Chat(String),
+    /// Message de chat privé, avec son expéditeur.
+    PrivateChat { message: String, sender: String },
+    /// Le joueur est désormais administrateur.
+    Admin,
+    /// Le dictionnaire a été rechargé.
+    Reloaded,
+    /// Mot ajouté au dictionnaire.
+    WordAdded(String),
+    /// Mot retiré du dictionnaire.
+    WordRemoved(String),
+    /// Définition demandée par le joueur.
+    Definition { word: String, definition: String },
+    /// Ouverture d'une contestation.
+    Challenge { challenger: String, word: String, kind: ChallengeKind },
+    /// Verdict de la contestation d'un mot par un joueur, suivi des scores.
+    Verdict { challenger: String, word: String, verdict: Verdict, scores: Scores },
+    /// Échec d'une requête du client: code de l'erreur et explication.
+    Error { code: String, message: String },
+}
+
+impl ServerMessage {
+    /// Renvoie la réponse signalant l'erreur `err` au client.
+    pub fn error(err: &ServerError) -> ServerMessage {
+        ServerMessage::Error { code: err.code().to_string(), message: err.to_string() }
+    }
+
+    /// Code le message dans le format texte, retour à la ligne final compris.
+    pub fn encode(&self) -> String {
+        match *self {
+            ServerMessage::Session => empty_message("SESSION"),
+            ServerMessage::Welcome { ref grid, turn, ref scores, ref bonus } => {
+                let scores = format!("{}{}{}", turn, LIST_SEP, scores_list(scores));
+                grid_message("BIENVENUE", grid, Some(scores), bonus)
+            },
+            ServerMessage::Connected(ref user) => message("CONNECTE", &[escape(user)]),
+            ServerMessage::Disconnected(ref user) => message("DECONNEXION", &[escape(user)]),
+            ServerMessage::Turn { ref grid, ref bonus } => grid_message("TOUR", grid, None, bonus),
+            ServerMessage::EndTurn => empty_message("RFIN"),
+            ServerMessage::TurnWords { ref words, ref scores } => {
+                let words = list(words.iter().flat_map(|(user, words)| {
+                    std::iter::once(user).chain(words)
+                }));
+                message("BILANMOTS", &[words, scores_list(scores)])
+            },
+            ServerMessage::MissedWords { ref words, percentage } =>
+                message("MANQUES", &[list(words), percentage.to_string()]),
+            ServerMessage::Definitions(ref definitions) =>
+                message("DEFINITIONS", &[list(definitions.iter().flat_map(|(w, d)| vec![w, d]))]),
+            ServerMessage::Winner(ref scores) => message("VAINQUEUR", &[scores_list(scores)]),
+            ServerMessage::ValidWord(ref word) => message("MVALIDE", &[escape(word)]),
+            ServerMessage::InvalidWord(ref reason) => message("MINVALIDE", &[escape(reason)]),
+            ServerMessage::Hint(hint) => message("INDICE", &[hint.kind().to_string(), escape(&hint.value()).into_owned()]),
+            ServerMessage::Chat(ref msg) => message("RECEPTION", &[escape(msg)]),
+            ServerMessage::PrivateChat { message: ref msg, ref sender } =>
+                message("PRECEPTION", &[escape(msg), escape(sender)]),
+            ServerMessage::Admin => empty_message("ADMIN"),
+            ServerMessage::Reloaded => empty_message("RECHARGE"),
+            ServerMessage::WordAdded(ref word) => message("AJOUTE", &[escape(word)]),
+            ServerMessage::WordRemoved(ref word) => message("RETIRE", &[escape(word)]),
+            ServerMessage::Definition { ref word, ref definition } =>
+                message("DEFINITION", &[escape(word), escape(definition)]),
+            ServerMessage::Challenge { ref challenger, ref word, ref kind } =>
+                message("CONTESTATION", &[escape(challenger), escape(word), kind.to_string().into()]),
+            ServerMessage::Verdict { ref challenger, ref word, verdict, ref scores } => {
+                let fields = [escape(challenger).into_owned(), escape(word).into_owned(), verdict.to_string(), scores_list(scores)];
+                message("VERDICT", &fields)
+            },
+            ServerMessage::Error { ref code, message: ref msg } => message("ERREUR", &[escape(code), escape(msg)]),
+        }
+    }
+}
+
+/// Format des messages échangés avec un client, choisi par sa première requête.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    /// Champs terminés par des `/`, le format historique.
+    Text,
+    /// Un objet JSON par ligne.
+    Json,
+}
+
+impl Encoding {
+    /// Code le message `message`, retour à la ligne final compris.
+    pub fn encode(self, message: &ServerMessage) -> String {
+        match self {
+            Encoding::Text => message.encode(),
+            Encoding::Json => json::encode(message),
+        }
+    }
+}
+
+/// Destination des messages envoyés à un client, codés dans le format qu'il a choisi.
+pub trait MessageSink {
+    fn send(&mut self, message: &ServerMessage) -> io::Result<()>;
+}
+
+/// Renvoie la liste des scores `scores`, chaque joueur suivi de son score.
+fn scores_list(scores: &Scores) -> String {
+    list(scores.iter().flat_map(|(user, score)| vec![user.clone(), score.to_string()]))
+}
+
+/// Renvoie le message `kind` contenant la grille `grid`, le champ `field` s'il existe, puis
+/// la disposition des cases bonus `bonus` si la grille en a.
+fn grid_message(kind: &str, grid: &str, field: Option<String>, bonus: &Option<String>) -> String {
+    let mut fields = vec![grid.to_string()];
+    fields.extend(field);
+    fields.extend(bonus.clone());
+    message(kind, &fields)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hostile() -> Vec<&'static str> {
+        vec!["", "bob", "a/b", "x*y", "/", "*", "\\", "fin\\", "\\/", "\\*", "a\nb", "\\n",
+             "//**\\\\", "é/ç*ö", "PENVOI/alice/coucou/"]
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        for text in hostile() {
+            let escaped = escape(text);
+            assert!(! escaped.contains('\n'), "{:?}", text);
+            assert_eq!(unescape(&escaped), text);
+            assert_eq!(split(&escaped, FIELD_SEP).len(), 1, "{:?}", text);
+            assert_eq!(split(&escaped, LIST_SEP).len(), 1, "{:?}", text);
+        }
+        assert_eq!(escape("a/b*c\\d"), "a\\/b\\*c\\\\d");
+        assert!(matches!(escape("trident"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn list_round_trip() {
+        let items = hostile();
+        assert_eq!(decode_list(&list(&items)), items);
+        assert_eq!(decode_list(&list(["alice", "12", "b*b", "3"])), vec!["alice", "12", "b*b", "3"]);
+        assert_eq!(list(["alice", "12"]), "alice*12");
+        assert_eq!(decode_list(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn message_round_trip() {
+        for text in hostile() {
+            let scores = list([text, "3", "bob", "0"]);
+            let msg = message("VAINQUEUR", &[escape(text).into_owned(), scores]);
+            assert_eq!(msg.matches('\n').count(), 1);
+            let fields = decode_message(msg.trim_end_matches('\n'));
+            assert_eq!(fields.len(), 4, "{:?}", msg);
+            assert_eq!(fields[0], "VAINQUEUR");
+            assert_eq!(unescape(fields[1]), text);
+            assert_eq!(decode_list(fields[2]), vec![text, "3", "bob", "0"]);
+            assert_eq!(fields[3], "");
+        }
+        assert_eq!(empty_message("RFIN"), "RFIN/\n");
+        assert_eq!(message("CONNECTE", &["bob"]), "CONNECTE/bob/\n");
+    }
+
+    #[test]
+    fn encode_messages() {
+        let scores = vec![("alice".to_string(), 3), ("b*b".to_string(), 0)];
+        let cases = vec![
+            (ServerMessage::Session, "SESSION/\n"),
+            (ServerMessage::Welcome { grid: "LIDA".to_string(), turn: 2, scores: scores.clone(), bonus: None },
+             "BIENVENUE/LIDA/2*alice*3*b\\*b*0/\n"),
+            (ServerMessage::Welcome { grid: "LIDA".to_string(), turn: 1, scores: vec![], bonus: Some("..L.".to_string()) },
+             "BIENVENUE/LIDA/1*/..L./\n"),
+            (ServerMessage::Connected("c/d".to_string()), "CONNECTE/c\\/d/\n"),
+            (ServerMessage::Disconnected("bob".to_string()), "DECONNEXION/bob/\n"),
+            (ServerMessage::Turn { grid: "LIDA".to_string(), bonus: None }, "TOUR/LIDA/\n"),
+            (ServerMessage::EndTurn, "RFIN/\n"),
+            (ServerMessage::TurnWords {
+                words: vec![("alice".to_string(), vec!["lid".to_string(), "ride".to_string()]),
+                            ("b*b".to_string(), vec![])],
+                scores: scores.clone(),
+            }, "BILANMOTS/alice*lid*ride*b\\*b/alice*3*b\\*b*0/\n"),
+            (ServerMessage::MissedWords { words: vec![], percentage: 100 }, "MANQUES//100/\n"),
+            (ServerMessage::Definitions(vec![("ile".to_string(), "Terre/île".to_string())]),
+             "DEFINITIONS/ile*Terre\\/île/\n"),
+            (ServerMessage::Winner(scores.clone()), "VAINQUEUR/alice*3*b\\*b*0/\n"),
+            (ServerMessage::ValidWord("lid".to_string()), "MVALIDE/lid/\n"),
+            (ServerMessage::InvalidWord("déjà joué".to_string()), "MINVALIDE/déjà joué/\n"),
+            (ServerMessage::Hint(Hint::Length(7)), "INDICE/LONGUEUR/7/\n"),
+            (ServerMessage::Hint(Hint::FirstLetter('t')), "INDICE/LETTRE/t/\n"),
+            (ServerMessage::Chat("a/b".to_string()), "RECEPTION/a\\/b/\n"),
+            (ServerMessage::PrivateChat { message: "salut".to_string(), sender: "bob".to_string() },
+             "PRECEPTION/salut/bob/\n"),
+            (ServerMessage::Admin, "ADMIN/\n"),
+            (ServerMessage::Reloaded, "RECHARGE/\n"),
+            (ServerMessage::WordAdded("lid".to_string()), "AJOUTE/lid/\n"),
+            (ServerMessage::WordRemoved("lid".to_string()), "RETIRE/lid/\n"),
+            (ServerMessage::Definition { word: "ile".to_string(), definition: "Terre.".to_string() },
+             "DEFINITION/ile/Terre./\n"),
+            (ServerMessage::Challenge { challenger: "bob".to_string(), word: "lid".to_string(),
+                                        kind: ChallengeKind::Rejected },
+             "CONTESTATION/bob/lid/AJOUT/\n"),
+            (ServerMessage::Verdict { challenger: "bob".to_string(), word: "lid".to_string(),
+                                      verdict: Verdict::Upheld, scores },
+             "VERDICT/bob/lid/ACCEPTEE/alice*3*b\\*b*0/\n"),
+            (ServerMessage::error(&ServerError::non_existing_user("a/b")),
+             "ERREUR/UTILISATEUR_INCONNU/L'utilisateur a\\/b n'existe pas./\n"),
+            (ServerMessage::error(&ServerError::invalid_chat("bob", "eve", "salut", ServerError::non_existing_user("eve"))),
+             "ERREUR/UTILISATEUR_INCONNU/Le message <salut> soumis par bob n'a pas pu être envoyé à eve: \
+              L'utilisateur eve n'existe pas./\n"),
+        ];
+        for (message, encoded) in cases {
+            assert_eq!(message.encode(), encoded);
+        }
+    }
+
+    #[test]
+    fn lenient_decoding() {
+        assert_eq!(unescape("fin\\"), "fin\\");
+        assert_eq!(unescape("\\a\\b"), "ab");
+        assert_eq!(split("a\\", FIELD_SEP), vec!["a\\"]);
+        assert_eq!(split("a//b", FIELD_SEP), vec!["a", "", "b"]);
+    }
+}
@@ -1,83 +1,178 @@
+#[allow(clippy::module_inception)]
 pub mod server;
 
 pub use self::server::{Server, Request};
 
 use super::{
     log::*,
-    board::*,
-    players::*,
     game::Game,
     errors::ServerError,
-    cloneable_stream::CloneableWriter,
+    client_writer::ClientWriter,
+    protocol::{self, Encoding, MessageSink, ServerMessage},
 };
 
 use std::{
-    io::{BufReader, BufRead},
-    sync::{Mutex,mpsc::{Sender, Receiver}, Arc},
-    thread::{self, JoinHandle},
-    marker::Sync,
-    net::TcpStream,
+    sync::{Mutex, mpsc::Sender, Arc},
     time::Duration,
 };
 
-pub fn run(server: Arc<Server>, streams: Receiver<TcpStream>) {
-    for sock in streams {
-        let s = server.clone();
-        thread::spawn(move || {
-            start_connection(s, sock);
-        });
+use serde_json::Value;
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::{self, JoinHandle},
+    time,
+};
+
+/// Accepte les connexions des clients, chacune traitée par sa propre tâche.
+pub async fn run(server: Arc<Server>, listener: TcpListener) {
+    loop {
+        match listener.accept().await {
+            Ok((sock, _)) => { tokio::spawn(start_connection(server.clone(), sock)); },
+            Err(e) => eprintln!("Error while accepting connection: {}", e),
+        }
     }
 }
 
 /// Démarre la connexion entre le serveur et un client.
-fn start_connection(server: Arc<Server>, stream: TcpStream) {
-    let mut reader = BufReader::new(stream.try_clone().unwrap());
-    let writer = CloneableWriter::new(stream);
-    let username;
+async fn start_connection(server: Arc<Server>, stream: TcpStream) {
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut writer = ClientWriter::new(writer);
+    let mut closing = writer.closing();
 
-    match connect(server.clone(), writer.clone(), &mut reader) {
-        Ok(name) => username = name,
-        Err(e) => { server.log(LogMsg::Error(e)); return; }
-    }
+    let username = match connect(server.clone(), &mut writer, &mut reader).await {
+        Ok(name) => name,
+        Err(e) => {
+            server.reply(&mut writer.clone(), &ServerMessage::error(&e));
+            writer.shutdown();
+            server.log(LogMsg::Error(e));
+            return;
+        }
+    };
 
-    for req in reader.lines() {
-        if let Ok(r) = req {
-            server.handle_client_request(&r, &username, writer.clone())
+    let mut lines = reader.lines();
+    loop {
+        let request = tokio::select! {
+            line = lines.next_line() => line,
+            _ = closing.wait_for(|&closed| closed) => break,
+        };
+        match request {
+            // Le traitement peut interroger un dictionnaire distant: il ne doit pas
+            // bloquer les autres tâches du même thread.
+            Ok(Some(r)) => task::block_in_place(|| server.handle_client_request(&r, &username, writer.clone())),
+            _ => break,
         }
     }
-    server.remove_user_if_connected(&username);
+    task::block_in_place(|| server.remove_user_if_connected(&username));
 }
 
 /// Lit la première requête du client et la traite si c'est une requête de connexion.
-/// Renvoie une erreur sinon.
-fn connect(server: Arc<Server>, stream: CloneableWriter, reader: &mut BufReader<TcpStream>)
+/// Le client peut d'abord choisir le format des messages avec la requête `PROTOCOLE/JSON/`:
+/// `stream` utilise alors ce format pour toute la suite de la connexion.
+/// Renvoie une erreur sinon, ou si la connexion est refusée.
+async fn connect<R: AsyncBufRead + Unpin>(server: Arc<Server>, stream: &mut ClientWriter, reader: &mut R)
            -> Result<String, ServerError>
 {
-    if server.nb_players() == 0 {
-        start_session(server.clone(), server.nb_turn(), server.turn_duration(), server.pause_duration());
+    let mut req = read_request(reader).await?;
+    if let Some(encoding) = parse_protocole(&req)? {
+        stream.set_encoding(encoding);
+        req = read_request(reader).await?;
     }
-    let mut req = String::new();
-    reader.read_line(&mut req).unwrap();
-    match parse_request(&req) {
+    match parse_request(&req, stream.encoding()) {
         Ok(Request::Login(username)) => {
-            server.login(&username, stream)?;
+            // La connexion attend les verrous du jeu, pris par le solveur pendant le tirage d'une grille.
+            task::block_in_place(|| server.login(&username, stream.clone()))?;
+            if server.claim_session() {
+                start_session(server.clone(), server.nb_turn(), server.turn_duration(), server.pause_duration());
+            }
             Ok(username)
         },
-        _ => Err(ServerError::unauthorized_request(&req))
+        _ => Err(ServerError::unauthorized_request(req.trim_end()))
+    }
+}
+
+/// Lit une requête du client.
+async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String, ServerError> {
+    let mut req = String::new();
+    reader.read_line(&mut req).await.map_err(|_| ServerError::unauthorized_request(req.trim_end()))?;
+    Ok(req)
+}
+
+/// Renvoie le format choisi par la requête `req` si c'est une requête `PROTOCOLE`.
+fn parse_protocole(req: &str) -> Result<Option<Encoding>, ServerError> {
+    let components = protocol::decode_message(req);
+    if components.first() != Some(&"PROTOCOLE") {
+        return Ok(None)
     }
+    match components.get(1) {
+        Some(&"TEXTE") => Ok(Some(Encoding::Text)),
+        Some(&"JSON") => Ok(Some(Encoding::Json)),
+        _ => Err(ServerError::bad_request(req.trim_end())),
+    }
+}
+
+/// Parse la requête d'un client, écrite dans le format `encoding`.
+fn parse_request(req: &str, encoding: Encoding) -> Result<Request, ServerError> {
+    match encoding {
+        Encoding::Text => parse_text_request(req),
+        Encoding::Json => parse_json_request(req),
+    }
+}
+
+/// Parse une requête au format JSON: un objet dont le champ `type` porte le nom de la requête
+/// dans le format texte, et les autres champs ses paramètres.
+fn parse_json_request(req: &str) -> Result<Request, ServerError> {
+    let err = ServerError::bad_request(req.trim_end());
+    let value = serde_json::from_str::<Value>(req).map_err(|_| err.clone())?;
+    let field = |name: &str| value.get(name).and_then(Value::as_str).map(|s| s.to_string()).ok_or(());
+
+    let request = match value.get("type").and_then(Value::as_str).ok_or(err.clone())? {
+        "CONNEXION" => field("pseudo").map(Request::Login),
+        "SORT" => field("pseudo").map(Request::Logout),
+        "TROUVE" => field("mot").and_then(|w| field("trajectoire").map(|t| Request::Found(w, t))),
+        "ENVOI" => field("message").map(Request::ChatAll),
+        "PENVOI" => field("destinataire").and_then(|u| field("message").map(|m| Request::Chat(u, m))),
+        "INDICE" => Ok(Request::Hint),
+        "ADMIN" => field("mdp").map(Request::Admin),
+        "RECHARGER" => Ok(Request::Reload),
+        "AJOUTER" => field("mot").map(Request::AddWord),
+        "RETIRER" => field("mot").map(Request::RemoveWord),
+        "CONTESTE" => field("mot").map(Request::Challenge),
+        "VOTE" => field("contestataire").and_then(|c| {
+            let approve = value.get("accepte").and_then(Value::as_bool).ok_or(())?;
+            field("mot").map(|w| Request::Vote(c, w, approve))
+        }),
+        "DEFINITION" => field("mot").map(Request::Definition),
+        _ => Err(())
+    };
+
+    request.map_err(|_| err)
 }
 
-/// Parse la requête d'un client.
-fn parse_request(req: &str) -> Result<Request, ServerError> {
-    let components: Vec<&str> = req.split("/").collect();
+/// Parse une requête au format texte.
+fn parse_text_request(req: &str) -> Result<Request, ServerError> {
+    let components = protocol::decode_message(req).into_iter()
+        .map(|c| protocol::unescape(c).into_owned())
+        .collect::<Vec<String>>();
+    let components = components.iter().map(|c| c.as_str()).collect::<Vec<&str>>();
     let err = ServerError::bad_request(req);
 
-    let request = match components.get(0).ok_or(err.clone())? {
-        &"CONNEXION" => parse_connexion(&components),
-        &"SORT" => parse_sort(&components),
-        &"TROUVE" => parse_trouve(&components),
-        &"ENVOI" => parse_envoi(&components),
-        &"PENVOI" => parse_penvoi(&components),
+    let request = match *components.first().ok_or(err.clone())? {
+        "CONNEXION" => parse_connexion(&components),
+        "SORT" => parse_sort(&components),
+        "TROUVE" => parse_trouve(&components),
+        "ENVOI" => parse_envoi(&components),
+        "PENVOI" => parse_penvoi(&components),
+        "INDICE" => Ok(Request::Hint),
+        "ADMIN" => parse_admin(&components),
+        "RECHARGER" => Ok(Request::Reload),
+        "AJOUTER" => parse_ajouter(&components),
+        "RETIRER" => parse_retirer(&components),
+        "CONTESTE" => parse_conteste(&components),
+        "VOTE" => parse_vote(&components),
+        "DEFINITION" => parse_definition(&components),
         _ => Err(())
     };
 
@@ -100,6 +195,42 @@ fn parse_trouve(components: &[&str]) -> Result<Request, ()> {
     Ok(Request::Found(word.to_string(), trajectory.to_string()))
 }
 
+fn parse_admin(components: &[&str]) -> Result<Request, ()> {
+    let password = components.get(1).ok_or(())?;
+    Ok(Request::Admin(password.to_string()))
+}
+
+fn parse_ajouter(components: &[&str]) -> Result<Request, ()> {
+    let word = components.get(1).ok_or(())?;
+    Ok(Request::AddWord(word.to_string()))
+}
+
+fn parse_retirer(components: &[&str]) -> Result<Request, ()> {
+    let word = components.get(1).ok_or(())?;
+    Ok(Request::RemoveWord(word.to_string()))
+}
+
+fn parse_conteste(components: &[&str]) -> Result<Request, ()> {
+    let word = components.get(1).ok_or(())?;
+    Ok(Request::Challenge(word.to_string()))
+}
+
+fn parse_vote(components: &[&str]) -> Result<Request, ()> {
+    let challenger = components.get(1).ok_or(())?;
+    let word = components.get(2).ok_or(())?;
+    let approve = match *components.get(3).ok_or(())? {
+        "OUI" => true,
+        "NON" => false,
+        _ => return Err(())
+    };
+    Ok(Request::Vote(challenger.to_string(), word.to_string(), approve))
+}
+
+fn parse_definition(components: &[&str]) -> Result<Request, ()> {
+    let word = components.get(1).ok_or(())?;
+    Ok(Request::Definition(word.to_string()))
+}
+
 fn parse_envoi(components: &[&str]) -> Result<Request, ()> {
     let message = components.get(1).ok_or(())?;
     Ok(Request::ChatAll(message.to_string()))
@@ -111,23 +242,251 @@ fn parse_penvoi(components: &[&str]) -> Result<Request, ()> {
     Ok(Request::Chat(user.to_string(), message.to_string()))
 }
 
-/// Démarre une Session dans un thread.
+/// Démarre une Session dans une tâche.
 fn start_session(server: Arc<Server>, nb_turn: u64, turn: Duration, pause: Duration)
     -> JoinHandle<()>
 {
-    thread::spawn(move || {
+    // Le tirage d'une grille filtrée peut parcourir le dictionnaire de nombreuses fois, et chaque
+    // étape attend les verrous du jeu: aucune ne doit bloquer les autres tâches du même thread.
+    tokio::spawn(async move {
         loop {
-            server.start_game_session();
+            task::block_in_place(|| server.start_game_session());
             for _ in 0..nb_turn {
-                server.new_game_turn();
-                thread::sleep(turn);
-                server.end_game_turn();
-                thread::sleep(pause);
-                if server.nb_players() == 0 {
+                task::block_in_place(|| server.new_game_turn());
+                time::sleep(turn).await;
+                task::block_in_place(|| server.end_game_turn());
+                time::sleep(pause).await;
+                if task::block_in_place(|| server.end_game_session_if_empty()) {
                     return;
                 }
             }
-            server.end_game_session();
+            task::block_in_place(|| server.end_game_session());
         }
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::server::test::create_test_server;
+
+    use std::sync::mpsc::Receiver;
+
+    use tokio::io::{duplex, AsyncWriteExt};
+
+    /// Connecte au serveur `server` un client envoyant les lignes `requests`.
+    /// Renvoie le résultat de la connexion et le flux d'écriture vers le client.
+    async fn connect_client(server: Arc<Server>, requests: &str) -> (Result<String, ServerError>, ClientWriter) {
+        let (stream, mut client) = duplex(4096);
+        client.write_all(requests.as_bytes()).await.unwrap();
+        let (reader, writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        let mut writer = ClientWriter::new(writer);
+        let result = connect(server, &mut writer, &mut reader).await;
+        (result, writer)
+    }
+
+    fn parse_json(req: &str) -> Request {
+        parse_json_request(req).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn parse_text(req: &str) -> Request {
+        parse_text_request(req).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn text_requests() {
+        assert_eq!(parse_text("CONNEXION/bob/\n"), Request::Login("bob".to_string()));
+        assert_eq!(parse_text("TROUVE/lid/A1A2A3/"), Request::Found("lid".to_string(), "A1A2A3".to_string()));
+        assert_eq!(parse_text("VOTE/bob/lid/NON/"), Request::Vote("bob".to_string(), "lid".to_string(), false));
+        assert_eq!(parse_text("INDICE/"), Request::Hint);
+    }
+
+    #[test]
+    fn text_requests_are_unescaped() {
+        assert_eq!(parse_text(r"CONNEXION/a\/b/"), Request::Login("a/b".to_string()));
+        assert_eq!(parse_text(r"PENVOI/x\*y/a\/b\\/"), Request::Chat("x*y".to_string(), r"a/b\".to_string()));
+        assert_eq!(parse_text(r"ENVOI/ligne 1\nligne 2 \/ fin/"), Request::ChatAll("ligne 1\nligne 2 / fin".to_string()));
+        assert_eq!(parse_text(r"AJOUTER/\\\\/"), Request::AddWord(r"\\".to_string()));
+    }
+
+    #[test]
+    fn invalid_text_requests() {
+        for req in &["", "INCONNU/", "CONNEXION", "PENVOI/bob", "VOTE/bob/lid/PEUTETRE/", r"CONNEXION\/bob/"] {
+            match parse_text_request(req) {
+                Err(e) => assert_eq!(e.code(), "REQUETE_INVALIDE"),
+                Ok(r) => panic!("{} is invalid, parsed as {:?}", req, r),
+            }
+        }
+    }
+
+    #[test]
+    fn protocole_requests() {
+        assert_eq!(parse_protocole("PROTOCOLE/JSON/\n").unwrap(), Some(Encoding::Json));
+        assert_eq!(parse_protocole("PROTOCOLE/TEXTE/").unwrap(), Some(Encoding::Text));
+        assert_eq!(parse_protocole("CONNEXION/bob/").unwrap(), None);
+        assert_eq!(parse_protocole("{\"type\":\"CONNEXION\"}").unwrap(), None);
+        for req in &["PROTOCOLE/XML/", "PROTOCOLE/json/", "PROTOCOLE/"] {
+            match parse_protocole(req) {
+                Err(e) => assert_eq!(e.code(), "REQUETE_INVALIDE"),
+                Ok(r) => panic!("{} is invalid, parsed as {:?}", req, r),
+            }
+        }
+    }
+
+    #[test]
+    fn json_requests() {
+        let requests = vec![
+            (r#"{"type":"CONNEXION","pseudo":"a/b"}"#, Request::Login("a/b".to_string())),
+            (r#"{"type":"SORT","pseudo":"bob"}"#, Request::Logout("bob".to_string())),
+            (r#"{"type":"TROUVE","mot":"lid","trajectoire":"A1A2A3"}"#,
+             Request::Found("lid".to_string(), "A1A2A3".to_string())),
+            (r#"{"type":"ENVOI","message":"a*b\nc"}"#, Request::ChatAll("a*b\nc".to_string())),
+            (r#"{"type":"PENVOI","destinataire":"bob","message":"salut"}"#,
+             Request::Chat("bob".to_string(), "salut".to_string())),
+            (r#"{"type":"INDICE"}"#, Request::Hint),
+            (r#"{"type":"ADMIN","mdp":"secret"}"#, Request::Admin("secret".to_string())),
+            (r#"{"type":"RECHARGER"}"#, Request::Reload),
+            (r#"{"type":"AJOUTER","mot":"lid"}"#, Request::AddWord("lid".to_string())),
+            (r#"{"type":"RETIRER","mot":"lid"}"#, Request::RemoveWord("lid".to_string())),
+            (r#"{"type":"CONTESTE","mot":"lid"}"#, Request::Challenge("lid".to_string())),
+            (r#"{"type":"VOTE","contestataire":"bob","mot":"lid","accepte":true}"#,
+             Request::Vote("bob".to_string(), "lid".to_string(), true)),
+            (r#"{"type":"DEFINITION","mot":"lid"}"#, Request::Definition("lid".to_string())),
+        ];
+        for (req, expected) in requests {
+            assert_eq!(parse_json(&format!("{}\n", req)), expected);
+        }
+    }
+
+    #[test]
+    fn json_requests_need_their_fields() {
+        let requests = [
+            r#"{"type":"CONNEXION"}"#, r#"{"type":"SORT","pseudo":3}"#, r#"{"type":"TROUVE","mot":"lid"}"#,
+            r#"{"type":"TROUVE","trajectoire":"A1A2A3"}"#, r#"{"type":"ENVOI"}"#,
+            r#"{"type":"PENVOI","message":"salut"}"#, r#"{"type":"PENVOI","destinataire":"bob"}"#,
+            r#"{"type":"ADMIN"}"#, r#"{"type":"AJOUTER"}"#, r#"{"type":"RETIRER"}"#, r#"{"type":"CONTESTE"}"#,
+            r#"{"type":"VOTE","contestataire":"bob","mot":"lid"}"#, r#"{"type":"VOTE","mot":"lid","accepte":true}"#,
+            r#"{"type":"VOTE","contestataire":"bob","mot":"lid","accepte":"OUI"}"#,
+            r#"{"type":"DEFINITION"}"#,
+        ];
+        for req in requests.iter() {
+            match parse_json_request(req) {
+                Err(e) => assert_eq!(e.code(), "REQUETE_INVALIDE"),
+                Ok(r) => panic!("{} is invalid, parsed as {:?}", req, r),
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_json_requests() {
+        for req in &["", "CONNEXION/bob/", "{\"type\":\"CONNEXION\",", "[\"CONNEXION\"]", "{\"pseudo\":\"bob\"}",
+                     "{\"type\":\"INCONNU\"}", "{\"type\":1}"] {
+            match parse_json_request(req) {
+                Err(e) => assert_eq!(e.code(), "REQUETE_INVALIDE"),
+                Ok(r) => panic!("{} is invalid, parsed as {:?}", req, r),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_with_json_protocol() {
+        let (server, _logs) = create_test_server();
+        let requests = "PROTOCOLE/JSON/\n{\"type\":\"CONNEXION\",\"pseudo\":\"bob\"}\n";
+        let (result, writer) = connect_client(Arc::new(server), requests).await;
+        assert_eq!(result.unwrap(), "bob");
+        assert_eq!(writer.encoding(), Encoding::Json);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_with_text_protocol() {
+        let (server, _logs) = create_test_server();
+        let server = Arc::new(server);
+        let (result, writer) = connect_client(server.clone(), "PROTOCOLE/TEXTE/\nCONNEXION/bob/\n").await;
+        assert_eq!(result.unwrap(), "bob");
+        assert_eq!(writer.encoding(), Encoding::Text);
+        let (result, writer) = connect_client(server, "CONNEXION/alice/\n").await;
+        assert_eq!(result.unwrap(), "alice");
+        assert_eq!(writer.encoding(), Encoding::Text);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_with_unknown_protocol() {
+        let (server, _logs) = create_test_server();
+        let (result, _) = connect_client(Arc::new(server), "PROTOCOLE/XML/\nCONNEXION/bob/\n").await;
+        assert_eq!(result.unwrap_err().code(), "REQUETE_INVALIDE");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_requests_login_in_chosen_protocol() {
+        let (server, _logs) = create_test_server();
+        let (result, _) = connect_client(Arc::new(server), "PROTOCOLE/JSON/\nCONNEXION/bob/\n").await;
+        assert_eq!(result.unwrap_err().code(), "NON_CONNECTE");
+    }
+
+    /// Attend le message de log vérifiant `pred`, en ignorant les autres.
+    /// Renvoie false si aucun n'arrive avant une seconde.
+    fn wait_for_log<F: Fn(&LogMsg) -> bool>(logs: &Receiver<LogMsg>, pred: F) -> bool {
+        task::block_in_place(|| {
+            while let Ok(msg) = logs.recv_timeout(Duration::from_secs(1)) {
+                if pred(&msg) {
+                    return true
+                }
+            }
+            false
+        })
+    }
+
+    /// Connecte à l'adresse `addr` l'utilisateur `name` et attend son message de bienvenue,
+    /// envoyé au début du tour suivant si aucun tour n'est en cours.
+    async fn login_client(addr: std::net::SocketAddr, name: &str) -> BufReader<TcpStream> {
+        let mut stream = BufReader::new(TcpStream::connect(addr).await.unwrap());
+        stream.get_mut().write_all(format!("CONNEXION/{}/\n", name).as_bytes()).await.unwrap();
+        loop {
+            let mut line = String::new();
+            time::timeout(Duration::from_secs(1), stream.read_line(&mut line)).await.unwrap().unwrap();
+            if line.starts_with("BIENVENUE/") {
+                return stream
+            }
+            assert!(! line.is_empty(), "Connexion refusée");
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn session_restarts_after_reconnection() {
+        let (server, logs) = create_test_server();
+        let server = server
+            .with_turn_duration(Duration::from_millis(20))
+            .with_pause_duration(Duration::from_millis(20));
+        let server = Arc::new(server);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run(server.clone(), listener));
+
+        let client = login_client(addr, "user1").await;
+        assert!(wait_for_log(&logs, |m| matches!(m, LogMsg::SessionStart(_))));
+        assert_eq!(server.nb_players(), 1);
+
+        drop(client);
+        assert!(wait_for_log(&logs, |m| matches!(m, LogMsg::SessionEnd)));
+        assert_eq!(server.nb_players(), 0);
+
+        let _client = login_client(addr, "user1").await;
+        assert!(wait_for_log(&logs, |m| matches!(m, LogMsg::SessionStart(_))));
+        assert_eq!(server.nb_players(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn simultaneous_logins_start_one_session() {
+        let (server, logs) = create_test_server();
+        let server = Arc::new(server);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run(server.clone(), listener));
+
+        let (_client1, _client2) = tokio::join!(login_client(addr, "user1"), login_client(addr, "user2"));
+        assert!(wait_for_log(&logs, |m| matches!(m, LogMsg::SessionStart(_))));
+        assert!(! wait_for_log(&logs, |m| matches!(m, LogMsg::SessionStart(_))));
+        assert_eq!(server.nb_players(), 2);
+    }
+}
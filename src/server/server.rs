@@ -1,44 +1,51 @@
 use super::*;
-use super::super::dict::LocalDict;
-use std::io::prelude::*;
+use std::collections::HashSet;
 
+#[derive(Debug, PartialEq)]
 pub enum Request {
     Login(String),
     Logout(String),
     Found(String, String),
     Chat(String, String),
     ChatAll(String),
+    Hint,
+    Admin(String),
+    Reload,
+    AddWord(String),
+    RemoveWord(String),
+    Challenge(String),
+    Vote(String, String, bool),
+    Definition(String),
 }
 
 pub struct Server {
-    game: Game<CloneableWriter>,
+    game: Game<ClientWriter>,
     logger: Sender<LogMsg>,
     nb_players: Mutex<usize>,
+    /// Si true, une session de jeu est en cours.
+    session_running: Mutex<bool>,
     nb_turn: u64,
     turn_duration: Duration,
     pause_duration: Duration,
+    admin_password: Option<String>,
+    admins: Mutex<HashSet<String>>,
 }
 
 impl Server {
-    pub fn new(logger: Sender<LogMsg>) -> Server {
-        let players = Players::new();
-        let board = Board::new(true, vec![]);
-        let game = Game::new(players, board, LocalDict::new());
+    pub fn new(game: Game<ClientWriter>, logger: Sender<LogMsg>) -> Server {
         Server {
             game,
             logger,
             nb_players: Mutex::new(0),
+            session_running: Mutex::new(false),
             nb_turn: 10,
             turn_duration: Duration::from_secs(180),
             pause_duration: Duration::from_secs(15),
+            admin_password: None,
+            admins: Mutex::new(HashSet::new()),
         }
     }
 
-    pub fn with_game(mut self, game: Game<CloneableWriter>) -> Server {
-        self.game = game;
-        self
-    }
-
     pub fn with_turn_duration(mut self, duration: Duration) -> Server {
         self.turn_duration = duration;
         self
@@ -54,6 +61,13 @@ impl Server {
         self
     }
 
+    /// Définit le mot de passe permettant à un joueur de devenir administrateur.
+    /// Sans mot de passe, personne ne peut l'être.
+    pub fn with_admin_password(mut self, password: &str) -> Server {
+        self.admin_password = Some(password.to_string());
+        self
+    }
+
     pub fn nb_turn(&self) -> u64 {
         self.nb_turn
     }
@@ -67,25 +81,58 @@ impl Server {
     }
 
     pub fn nb_players(&self) -> usize {
-        self.nb_players.lock().unwrap().clone()
+        *self.nb_players.lock().unwrap()
+    }
+
+    /// Réserve le démarrage d'une session de jeu.
+    /// Renvoie false si une session est déjà en cours: elle ne doit pas être démarrée une seconde fois.
+    pub fn claim_session(&self) -> bool {
+        let mut running = self.session_running.lock().unwrap();
+        ! std::mem::replace(&mut *running, true)
+    }
+
+    /// Met fin à la session de jeu courante si plus aucun joueur n'est connecté.
+    /// Le verrou de la session est gardé jusqu'à la fin: une nouvelle session ne peut
+    /// démarrer qu'après celle-ci.
+    pub fn end_game_session_if_empty(&self) -> bool {
+        let mut running = self.session_running.lock().unwrap();
+        if self.nb_players() > 0 {
+            return false
+        }
+        self.end_game_session();
+        *running = false;
+        true
     }
 
     /// Démarre une session de jeu.
     pub fn start_game_session(&self) {
-        self.game.start_session();
-        self.log(LogMsg::SessionStart);
+        let seed = self.game.start_session();
+        self.log(LogMsg::SessionStart(seed));
     }
 
     /// Met fin à la session de jeu courante.
     pub fn end_game_session(&self) {
+        self.close_challenges();
         self.game.end_session();
         self.log(LogMsg::SessionEnd);
     }
 
+    /// Clôt les contestations restées en attente pendant la pause.
+    fn close_challenges(&self) {
+        for (challenger, word, verdict) in self.game.close_challenges() {
+            self.log(LogMsg::verdict(&challenger, &word, verdict));
+        }
+    }
+
     /// Démarre un nouveau tour.
     pub fn new_game_turn(&self) {
-        self.game.new_turn();
-        println!("start turn");
+        self.close_challenges();
+        let (stats, rejected) = self.game.new_turn();
+        rejected.into_iter().for_each(|r| self.log(LogMsg::GridRejected(r)));
+        if let Some(description) = self.game.grid_description() {
+            self.log(LogMsg::CatalogueGrid(description));
+        }
+        self.log(LogMsg::TurnStart(stats));
     }
 
     /// Met fin au tour courant.
@@ -94,59 +141,160 @@ impl Server {
     }
 
     /// Taite la requête `request` de l'utlisateur `username`.
-    /// La réponse éventuelle sera crite sur le stream `stream`.
-    pub fn handle_client_request(&self, request: &str, username: &str, mut stream: CloneableWriter) {
-        let result = parse_request(request).and_then(|r| {
+    /// La réponse éventuelle, ou l'erreur si la requête échoue, sera crite sur le stream `stream`.
+    pub fn handle_client_request(&self, request: &str, username: &str, mut stream: ClientWriter) {
+        let request = parse_request(request, stream.encoding());
+        let is_login = matches!(request, Ok(Request::Login(_)));
+        let result = request.and_then(|r| {
             match r {
-                Request::Login(name) => self.login(&name, stream),
-                Request::Logout(name) => self.logout(&name, stream),
+                Request::Login(name) => self.login(&name, stream.clone()),
+                Request::Logout(name) => self.logout(&name, stream.clone()),
                 Request::Found(word, trajectory) =>
-                    self.found(&username, &mut stream, &word, &trajectory),
+                    self.found(username, &mut stream, &word, &trajectory),
                 Request::Chat(to, message) => self.chat(username, &to, &message),
                 Request::ChatAll(message) => self.chat_all(username, &message),
+                Request::Hint => self.hint(username, &mut stream),
+                Request::Admin(password) => self.admin(username, &password, &mut stream),
+                Request::Reload => self.reload(username, &mut stream),
+                Request::AddWord(word) => self.add_word(username, &word, &mut stream),
+                Request::RemoveWord(word) => self.remove_word(username, &word, &mut stream),
+                Request::Challenge(word) => self.challenge(username, &word),
+                Request::Vote(challenger, word, approve) => self.vote(username, &challenger, &word, approve),
+                Request::Definition(word) => self.definition(username, &word, &mut stream),
             }
         });
         if let Err(e) = result {
-            self.log(LogMsg::err(e))
+            self.reply(&mut stream, &ServerMessage::error(&e));
+            self.log(LogMsg::err(e));
+            if is_login {
+                stream.shutdown();
+            }
         }
     }
 
     /// Enregsitre un nouvel utilisateur `username`.
-    pub fn login(&self, username: &str, writer: CloneableWriter) -> Result<(), ServerError> {
-        self.game.login(username, writer.clone())
+    /// En cas d'échec, la connexion doit être fermée par l'appelant après lui avoir signalé l'erreur.
+    pub fn login(&self, username: &str, writer: ClientWriter) -> Result<(), ServerError> {
+        self.game.login(username, writer)
             .map(|_|  { self.log(LogMsg::login(username)); *self.nb_players.lock().unwrap() += 1 })
-            .map_err(|e| { writer.shutdown(); e })
     }
 
     /// Supprime l'utlisateur `username` et clos la connexion.
-    pub fn logout(&self, username: &str, writer: CloneableWriter) -> Result<(), ServerError> {
+    pub fn logout(&self, username: &str, writer: ClientWriter) -> Result<(), ServerError> {
         self.game.logout(username).map(|_| {
             *self.nb_players.lock().unwrap() -= 1;
+            self.admins.lock().unwrap().remove(username);
             writer.shutdown();
             self.log(LogMsg::logout(username))
         })
     }
 
     /// Soumission du mot `word` de trajectoire `trajectory` par l'utilisateur `username`.
-    pub fn found(&self, username: &str, writer: &mut CloneableWriter, word: &str, trajectory: &str)
+    /// En vérification immédiate, un mot déjà joué reçoit la réponse `MINVALIDE` au lieu d'une erreur.
+    pub fn found(&self, username: &str, writer: &mut ClientWriter, word: &str, trajectory: &str)
              -> Result<(), ServerError>
     {
-        self.game.found(username, word, trajectory)
-            .map(|is_immediate| {
+        match self.game.found(username, word, trajectory) {
+            Ok(is_immediate) => {
                 if is_immediate {
-                    writer.write(format!("MVALIDE/{}/\n", word).as_bytes())
-                        .expect("Cannot write response");
+                    self.reply(writer, &ServerMessage::ValidWord(word.to_string()));
                 }
                 self.log(LogMsg::accepted(username, word));
-            })
-            .map_err(|e| {
-                if let ServerError::AlreadyPlayed {ref word, immediate: true} = e {
+                Ok(())
+            },
+            Err(e @ ServerError::AlreadyPlayed { immediate: true, .. }) => {
+                if let ServerError::AlreadyPlayed { ref word, .. } = e {
                     let msg = format!("PRI: le mot <{}> a déjà été joué !", word);
-                    writer.write(format!("MINVALIDE/{}/\n", msg).as_bytes())
-                        .expect("Cannot write response");
+                    self.reply(writer, &ServerMessage::InvalidWord(msg));
                 }
-                e
-            })
+                self.log(LogMsg::err(e));
+                Ok(())
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Envoi d'un indice à l'utilisateur `username`.
+    pub fn hint(&self, username: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        self.game.hint(username).map(|hint| {
+            self.reply(writer, &ServerMessage::Hint(hint));
+            self.log(LogMsg::hint(username, &hint));
+        })
+    }
+
+    /// Fait de l'utilisateur `username` un administrateur si `password` est le bon mot de passe.
+    pub fn admin(&self, username: &str, password: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        if self.admin_password.as_deref() != Some(password) {
+            return Err(ServerError::bad_admin_password(username))
+        }
+        self.admins.lock().unwrap().insert(username.to_string());
+        self.reply(writer, &ServerMessage::Admin);
+        self.log(LogMsg::admin(username));
+        Ok(())
+    }
+
+    /// Renvoie une erreur si l'utilisateur `username` n'est pas administrateur.
+    fn check_admin(&self, username: &str, request: &str) -> Result<(), ServerError> {
+        if self.admins.lock().unwrap().contains(username) {
+            Ok(())
+        } else {
+            Err(ServerError::not_admin(username, request))
+        }
+    }
+
+    /// Recharge les listes de mots du dictionnaire à la demande de l'administrateur `username`.
+    pub fn reload(&self, username: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        self.check_admin(username, "RECHARGER")?;
+        self.game.reload_dict()
+            .map_err(|e| ServerError::reload_failed(username, &e))?;
+        self.reply(writer, &ServerMessage::Reloaded);
+        self.log(LogMsg::dict_reloaded(username));
+        Ok(())
+    }
+
+    /// Ajout du mot `word` au dictionnaire par l'administrateur `username`.
+    pub fn add_word(&self, username: &str, word: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        self.check_admin(username, "AJOUTER")?;
+        self.game.add_word(word, username)
+            .map_err(|e| ServerError::dict_change_failed(username, word, &e))?;
+        self.reply(writer, &ServerMessage::WordAdded(word.to_string()));
+        self.log(LogMsg::word_added(username, word));
+        Ok(())
+    }
+
+    /// Retrait du mot `word` du dictionnaire par l'administrateur `username`.
+    pub fn remove_word(&self, username: &str, word: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        self.check_admin(username, "RETIRER")?;
+        self.game.remove_word(word, username)
+            .map_err(|e| ServerError::dict_change_failed(username, word, &e))?;
+        self.reply(writer, &ServerMessage::WordRemoved(word.to_string()));
+        self.log(LogMsg::word_removed(username, word));
+        Ok(())
+    }
+
+    /// Contestation du mot `word` par l'utilisateur `username`.
+    pub fn challenge(&self, username: &str, word: &str) -> Result<(), ServerError> {
+        self.game.challenge(username, word).map(|_| {
+            self.log(LogMsg::challenge(username, word));
+        })
+    }
+
+    /// Vote de l'utilisateur `username` sur la contestation du mot `word` par `challenger`.
+    pub fn vote(&self, username: &str, challenger: &str, word: &str, approve: bool) -> Result<(), ServerError> {
+        self.game.vote(username, challenger, word, approve).map(|verdict| {
+            self.log(LogMsg::vote(username, challenger, word, approve));
+            if let Some(verdict) = verdict {
+                self.log(LogMsg::verdict(challenger, word, verdict));
+            }
+        })
+    }
+
+    /// Envoi de la définition du mot `word` à l'utilisateur `username`.
+    pub fn definition(&self, username: &str, word: &str, writer: &mut ClientWriter) -> Result<(), ServerError> {
+        self.game.definition(word).map(|definition| {
+            self.reply(writer, &ServerMessage::Definition { word: word.to_string(), definition });
+            self.log(LogMsg::definition(username, word));
+        })
     }
 
     /// Envoi du message `msg` à l'utilisateur `receiver` par l'utilisateur `sender`.
@@ -167,16 +315,145 @@ impl Server {
     /// Déconnecte l'utilisateur `username` s'il était connecté.
     pub fn remove_user_if_connected(&self, username: &str) {
         if self.game.is_connected(username) {
-            if let Err(e) = self.game.logout(username) {
-                eprintln!("Error while logging out: {}", e)
+            self.admins.lock().unwrap().remove(username);
+            match self.game.logout(username) {
+                Ok(()) => {
+                    *self.nb_players.lock().unwrap() -= 1;
+                    self.log(LogMsg::logout(username))
+                },
+                Err(e) => eprintln!("Error while logging out: {}", e),
             }
         }
     }
 
+    /// Envoie la réponse `message` au client du flux `writer`.
+    pub fn reply(&self, writer: &mut ClientWriter, message: &ServerMessage) {
+        if let Err(e) = writer.send(message) {
+            eprintln!("Error while sending response: {}", e)
+        }
+    }
+
     /// Envoie une commande au Logger.
     pub fn log(&self, msg: LogMsg) {
         self.logger.send(msg).unwrap()
     }
 }
 
-unsafe impl Sync for Server { }
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use super::super::super::game::test::create_test_game;
+
+    use std::sync::mpsc::{channel, Receiver};
+
+    use tokio::{
+        io::{duplex, AsyncBufReadExt, BufReader, DuplexStream},
+        time,
+    };
+
+    /// Client connecté au serveur de test.
+    struct Client {
+        name: String,
+        writer: ClientWriter,
+        reader: BufReader<DuplexStream>,
+    }
+
+    impl Client {
+        /// Connecte l'utilisateur `name` et lit son message de bienvenue.
+        async fn login(server: &Server, name: &str) -> Client {
+            let (stream, client) = duplex(4096);
+            let writer = ClientWriter::new(stream);
+            server.login(name, writer.clone()).unwrap();
+            let mut client = Client { name: name.to_string(), writer, reader: BufReader::new(client) };
+            assert!(client.read_line().await.starts_with("BIENVENUE/"));
+            client
+        }
+
+        /// Envoie la requête `request` au serveur et renvoie la réponse.
+        async fn request(&mut self, server: &Server, request: &str) -> String {
+            server.handle_client_request(request, &self.name, self.writer.clone());
+            self.read_line().await
+        }
+
+        async fn read_line(&mut self) -> String {
+            let mut line = String::new();
+            time::timeout(Duration::from_secs(1), self.reader.read_line(&mut line)).await
+                .expect("Aucune réponse du serveur")
+                .unwrap();
+            line
+        }
+    }
+
+    pub fn create_test_server() -> (Server, Receiver<LogMsg>) {
+        let (logger, logs) = channel();
+        let server = Server::new(create_test_game(), logger).with_admin_password("mdp");
+        (server, logs)
+    }
+
+    #[tokio::test]
+    async fn already_played_word_gets_one_reply() {
+        let (server, _logs) = create_test_server();
+        let mut client1 = Client::login(&server, "user1").await;
+        let mut client2 = Client::login(&server, "user2").await;
+        assert_eq!(client1.read_line().await, "CONNECTE/user2/\n");
+        assert_eq!(client1.request(&server, "TROUVE/trident/C2B1A2A3B2C3D2/").await, "MVALIDE/trident/\n");
+        let reply = client2.request(&server, "TROUVE/trident/C2B1A2A3B2C3D2/").await;
+        assert_eq!(reply, "MINVALIDE/PRI: le mot <trident> a déjà été joué !/\n");
+        server.handle_client_request("ENVOI/fin/", "user2", client2.writer.clone());
+        assert_eq!(client2.read_line().await, "RECEPTION/fin/\n");
+    }
+
+    #[tokio::test]
+    async fn admin_requests_need_admin_rights() {
+        let (server, _logs) = create_test_server();
+        let mut client = Client::login(&server, "user1").await;
+        for request in &["AJOUTER/lid/", "RETIRER/ile/", "RECHARGER/"] {
+            assert!(client.request(&server, request).await.starts_with("ERREUR/NON_ADMIN/"));
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_with_wrong_password() {
+        let (server, _logs) = create_test_server();
+        let mut client = Client::login(&server, "user1").await;
+        assert!(client.request(&server, "ADMIN/faux/").await.starts_with("ERREUR/MDP_INVALIDE/"));
+        assert!(client.request(&server, "RECHARGER/").await.starts_with("ERREUR/NON_ADMIN/"));
+    }
+
+    #[tokio::test]
+    async fn admin_rights_are_dropped_on_logout() {
+        let (server, _logs) = create_test_server();
+        let mut client = Client::login(&server, "user1").await;
+        assert_eq!(client.request(&server, "ADMIN/mdp/").await, "ADMIN/\n");
+        assert_eq!(client.request(&server, "RECHARGER/").await, "RECHARGE/\n");
+        server.handle_client_request("SORT/user1/", "user1", client.writer.clone());
+
+        let mut client = Client::login(&server, "user1").await;
+        assert!(client.request(&server, "RECHARGER/").await.starts_with("ERREUR/NON_ADMIN/"));
+    }
+
+    #[tokio::test]
+    async fn admin_rights_are_dropped_on_disconnection() {
+        let (server, _logs) = create_test_server();
+        let mut client = Client::login(&server, "user1").await;
+        assert_eq!(client.request(&server, "ADMIN/mdp/").await, "ADMIN/\n");
+        server.remove_user_if_connected("user1");
+        assert_eq!(server.nb_players(), 0);
+
+        let mut client = Client::login(&server, "user1").await;
+        assert!(client.request(&server, "RECHARGER/").await.starts_with("ERREUR/NON_ADMIN/"));
+    }
+
+    #[tokio::test]
+    async fn only_one_session_at_a_time() {
+        let (server, _logs) = create_test_server();
+        assert!(server.claim_session());
+        assert!(! server.claim_session());
+        let _client = Client::login(&server, "user1").await;
+        assert!(! server.end_game_session_if_empty());
+        assert!(! server.claim_session());
+        server.remove_user_if_connected("user1");
+        assert!(server.end_game_session_if_empty());
+        assert!(server.claim_session());
+    }
+}